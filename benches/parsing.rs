@@ -0,0 +1,38 @@
+//! Compares the chumsky pipeline with the fast parsing path on large generated automata.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hoars::{
+    input::{from_hoa, from_hoa_fast},
+    output::to_hoa,
+    random::{random_automaton, RandomAcceptance, RandomOptions},
+};
+
+fn generated(states: usize) -> String {
+    let options = RandomOptions {
+        states,
+        aps: 4,
+        density: 0.05,
+        acceptance: RandomAcceptance::Parity(4),
+        ..Default::default()
+    };
+    to_hoa(&random_automaton(&options, 0).unwrap())
+}
+
+fn parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parsing");
+    group.sample_size(10);
+    for states in [100, 1_000] {
+        let input = generated(states);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("chumsky", states), &input, |b, input| {
+            b.iter(|| from_hoa(input).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("fast", states), &input, |b, input| {
+            b.iter(|| from_hoa_fast(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
@@ -0,0 +1,144 @@
+use biodivine_lib_bdd::{Bdd, BddVariable, BddVariableSet};
+
+use crate::{build_vars, symbolic::declared_aps, AbstractLabelExpression, HoaAutomaton, Id};
+
+/// The BDD universe of an automaton, which holds one variable per atomic proposition. The
+/// [`Bdd`]s of labels only have a meaning together with the alphabet they were built over,
+/// so labels are converted through it.
+#[derive(Debug, Clone)]
+pub struct Alphabet {
+    vs: BddVariableSet,
+    vars: Vec<BddVariable>,
+}
+
+impl Alphabet {
+    /// Creates an alphabet over the given number of atomic propositions. Fails if there are
+    /// more than [`u16::MAX`] atomic propositions.
+    pub fn new(aps: usize) -> Result<Self, String> {
+        let (vs, vars) = build_vars(aps)?;
+        Ok(Self { vs, vars })
+    }
+
+    /// Creates the alphabet over the atomic propositions declared by the automaton.
+    pub fn of(aut: &HoaAutomaton) -> Result<Self, String> {
+        Self::new(declared_aps(aut).len())
+    }
+
+    /// Returns the number of atomic propositions.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Returns true if there are no atomic propositions.
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Returns the underlying variable set.
+    pub fn variable_set(&self) -> &BddVariableSet {
+        &self.vs
+    }
+
+    /// Returns the variable of the atomic proposition with the given index.
+    pub fn variable(&self, ap: Id) -> Option<BddVariable> {
+        self.vars.get(ap as usize).copied()
+    }
+
+    /// Converts a label into a [`Bdd`] over this alphabet. Fails if the label refers to an
+    /// atomic proposition that is not part of the alphabet.
+    pub fn to_bdd(&self, label: &AbstractLabelExpression) -> Result<Bdd, String> {
+        label.clone().try_into_bdd(&self.vs, &self.vars)
+    }
+
+    /// Converts a [`Bdd`] over this alphabet back into a label in disjunctive normal form.
+    pub fn to_label(&self, bdd: &Bdd) -> AbstractLabelExpression {
+        AbstractLabelExpression::from_bdd(bdd, &self.vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::{
+        language::accepted_word,
+        output::{to_hoa, HoaWriter, PropertiesMode},
+        subset::subset_construction,
+        symbolic::Symbolic,
+        AbstractLabelExpression, HoaAutomaton,
+    };
+
+    use super::Alphabet;
+
+    /// A deterministic automaton over `aps` propositions whose states form a single cycle,
+    /// the edge of state `i` requires proposition `i` and forbids proposition `aps - 1 - i`.
+    fn many_aps(aps: usize) -> String {
+        let names = (0..aps).map(|i| format!("\"p{i}\"")).join(" ");
+        let mut out = format!("HOA: v1\nStates: {aps}\nStart: 0\nAP: {aps} {names}\n");
+        out.push_str("Acceptance: 0 t\n--BODY--\n");
+        for state in 0..aps {
+            out.push_str(&format!(
+                "State: {state}\n[{state} & !{}] {}\n",
+                aps - 1 - state,
+                (state + 1) % aps
+            ));
+        }
+        out.push_str("--END--\n");
+        out
+    }
+
+    #[test]
+    fn hundreds_of_aps() {
+        let input = many_aps(300);
+        let aut = HoaAutomaton::try_from(input.as_str()).unwrap();
+        assert_eq!(aut.num_aps(), 300);
+        assert!(aut.verify().is_ok());
+        assert_eq!(
+            HoaAutomaton::try_from(to_hoa(&aut).as_str()),
+            Ok(aut.clone())
+        );
+
+        let alphabet = Alphabet::of(&aut).unwrap();
+        assert_eq!(alphabet.len(), 300);
+        let label = &aut.body()[299].edges()[0].label().0;
+        let bdd = alphabet.to_bdd(label).unwrap();
+        assert_eq!(bdd.cardinality(), 2f64.powi(298));
+        assert_eq!(alphabet.to_bdd(&alphabet.to_label(&bdd)), Ok(bdd));
+        assert!(alphabet
+            .to_bdd(&AbstractLabelExpression::Integer(300))
+            .is_err());
+
+        let sym = Symbolic::from_automaton(&aut).unwrap();
+        assert!(sym.is_deterministic());
+        assert!(accepted_word(&aut).unwrap().is_some());
+    }
+
+    #[test]
+    fn alphabets_are_per_automaton() {
+        let small = HoaAutomaton::try_from(many_aps(2).as_str()).unwrap();
+        let large = HoaAutomaton::try_from(many_aps(200).as_str()).unwrap();
+        let (small, large) = (Alphabet::of(&small).unwrap(), Alphabet::of(&large).unwrap());
+        let label = AbstractLabelExpression::Integer(150);
+        assert!(small.to_bdd(&label).is_err());
+        assert_eq!(large.to_bdd(&label).unwrap().cardinality(), 2f64.powi(199));
+    }
+
+    #[test]
+    fn too_many_aps() {
+        let aps = u16::MAX as usize + 1;
+        assert!(Alphabet::new(aps).is_err());
+        let aut = HoaAutomaton::try_from(many_aps(1).as_str()).unwrap();
+        let mut header = aut.header().clone();
+        for item in header.iter_mut() {
+            if let crate::HeaderItem::AP(names) = item {
+                *names = (0..aps).map(|i| format!("p{i}")).collect();
+            }
+        }
+        let aut = HoaAutomaton::from_parts(header, aut.body().clone());
+        assert!(Alphabet::of(&aut).is_err());
+        assert!(Symbolic::from_automaton(&aut).is_err());
+        assert!(subset_construction(&aut).is_err());
+        let mut writer = HoaWriter::new(vec![]).properties(PropertiesMode::Recompute);
+        assert!(writer.write(&aut).is_err());
+    }
+}
@@ -1,14 +1,19 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    sync::OnceLock,
+};
 
 use chumsky::prelude::*;
 
 use crate::{
-    lexer::Token, value, AbstractLabelExpression, AcceptanceSignature, AtomicProposition, Id,
-    StateConjunction,
+    lexer::{Span, Token},
+    value, AbstractLabelExpression, AcceptanceSignature, Id, StateConjunction,
 };
 
-/// Newtype wrapper around a [`crate::LabelExpression`], implements [`Deref`].
+/// Newtype wrapper around a [`crate::AbstractLabelExpression`], implements [`Deref`].
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Label(pub AbstractLabelExpression);
 
 impl Deref for Label {
@@ -19,9 +24,6 @@ impl Deref for Label {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Alphabet(pub Vec<AtomicProposition>);
-
 #[derive(Clone, Debug)]
 pub struct RawState(
     Option<Label>,
@@ -36,7 +38,7 @@ struct ExplicitEdge(Label, StateConjunction, Option<AcceptanceSignature>);
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct ImplicitEdge(StateConjunction, Option<AcceptanceSignature>);
 
-/// Represents an edge in a HOA automaton. It contains the [`crate::LabelExpression`], the
+/// Represents an edge in a HOA automaton. It contains the [`crate::AbstractLabelExpression`], the
 /// [`StateConjunction`] and the [`AcceptanceSignature`] of the edge.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Edge(
@@ -167,16 +169,63 @@ fn explicit_edge() -> impl Parser<Token, ExplicitEdge, Error = Simple<Token>> {
         })
 }
 
-// For now, we allow only explicit edges, but this might change in the future. In any case, we already have the necessary
-// infrastructure to support implicit edges.
-#[allow(unused)]
 fn implicit_edge() -> impl Parser<Token, ImplicitEdge, Error = Simple<Token>> {
     value::state_conjunction()
         .then(value::acceptance_signature().or_not())
         .map(|(label, acceptance_signature)| ImplicitEdge(label, acceptance_signature))
 }
 
-pub fn state() -> impl Parser<Token, State, Error = Simple<Token>> {
+/// The label that is satisfied exactly by the given valuation of `aps` propositions, the
+/// first proposition is the least significant bit. This is the label of the edge at
+/// position `valuation` in a state with implicit labels.
+pub(crate) fn valuation_label(valuation: usize, aps: usize) -> Label {
+    let literal = |ap: usize| {
+        let positive = AbstractLabelExpression::Integer(ap as Id);
+        match valuation >> ap & 1 {
+            1 => positive,
+            _ => AbstractLabelExpression::Negated(Box::new(positive)),
+        }
+    };
+    Label(match aps {
+        0 => AbstractLabelExpression::Boolean(true),
+        1 => literal(0),
+        _ => AbstractLabelExpression::Conjunction((0..aps).map(literal).collect()),
+    })
+}
+
+/// Turns the edges of a state into explicit ones. Edges without a label are labelled with
+/// the valuation given by their position, which requires one edge per valuation of the
+/// `aps` atomic propositions.
+fn label_edges(
+    id: Id,
+    edges: Vec<Result<ExplicitEdge, ImplicitEdge>>,
+    aps: usize,
+) -> Result<Vec<ExplicitEdge>, String> {
+    if edges.iter().all(Result::is_ok) {
+        return Ok(edges.into_iter().flatten().collect());
+    }
+    if edges.iter().any(Result::is_ok) {
+        return Err(format!("State {id} mixes edges with and without labels"));
+    }
+    if 1usize.checked_shl(aps as u32) != Some(edges.len()) {
+        return Err(format!(
+            "State {id} has {} edges without labels, but implicit labels require one edge \
+             for each of the 2^{aps} valuations",
+            edges.len()
+        ));
+    }
+    Ok(edges
+        .into_iter()
+        .filter_map(Result::err)
+        .enumerate()
+        .map(|(valuation, edge)| ExplicitEdge(valuation_label(valuation, aps), edge.0, edge.1))
+        .collect())
+}
+
+/// Parses a state together with its span and the spans of its edges. Edges without labels
+/// are labelled implicitly over `aps` atomic propositions.
+#[allow(clippy::result_large_err)]
+fn state(aps: usize) -> impl Parser<Token, (State, Span, Vec<Span>), Error = Simple<Token>> {
     just(Token::Header("State".to_string()))
         .ignore_then(
             label()
@@ -186,13 +235,58 @@ pub fn state() -> impl Parser<Token, State, Error = Simple<Token>> {
                 .then(value::acceptance_signature().or_not())
                 .map(|(((l, i), t), a)| RawState(l, i, t, a)),
         )
-        .then(explicit_edge().repeated())
-        .try_map(|input, span| State::try_from(input).map_err(|err| Simple::custom(span, err)))
+        .then(
+            filter(|token| matches!(token, Token::Paren('[') | Token::Int(_)))
+                .rewind()
+                .ignore_then(
+                    explicit_edge()
+                        .map(Ok)
+                        .or(implicit_edge().map(Err))
+                        .map_with_span(|edge, span| Some((edge, span)))
+                        .recover_with(
+                            skip_until(
+                                [
+                                    Token::Paren('['),
+                                    Token::Header("State".to_string()),
+                                    Token::BodyEnd,
+                                ],
+                                |_| None,
+                            )
+                            .skip_start(),
+                        ),
+                )
+                .repeated(),
+        )
+        .try_map(move |(raw_state, edges), span| {
+            let (edges, edge_spans): (Vec<_>, Vec<_>) = edges.into_iter().flatten().unzip();
+            if raw_state.0.is_some() && edges.iter().any(Result::is_err) {
+                let message = format!(
+                    "State {} has a label and edges without labels, labelled states are not \
+                     supported",
+                    raw_state.1
+                );
+                return Err(Simple::custom(span, message));
+            }
+            label_edges(raw_state.1, edges, aps)
+                .and_then(|edges| State::try_from((raw_state, edges)))
+                .map(|state| (state, span.clone(), edge_spans))
+                .map_err(|err| Simple::custom(span, err))
+        })
 }
 
-/// Represents the body of a HOA automaton. In essence, this is just a vector of [`State`]s.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct Body(Vec<State>);
+/// The spans of all states of a body and the spans of their edges.
+pub(crate) type BodySpans = (Vec<Span>, Vec<Vec<Span>>);
+
+/// Represents the body of a HOA automaton. In essence, this is just a vector of [`State`]s,
+/// which is sorted by id unless it is modified through [`DerefMut`]. The positions of states
+/// with ids that differ from their position and the predecessors of each state are computed
+/// on demand and cached until the body is modified.
+#[derive(Clone, Default)]
+pub struct Body {
+    states: Vec<State>,
+    positions: OnceLock<HashMap<Id, usize>>,
+    predecessors: OnceLock<Vec<Vec<Id>>>,
+}
 
 impl<'a> IntoIterator for &'a Body {
     type Item = &'a State;
@@ -200,27 +294,144 @@ impl<'a> IntoIterator for &'a Body {
     type IntoIter = std::slice::Iter<'a, State>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.states.iter()
     }
 }
 
 impl Body {
     /// Constructs a new empty body.
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self::default()
     }
 
-    pub(crate) fn parser() -> impl Parser<Token, Self, Error = Simple<Token>> {
+    /// Returns the position of the state with the given id in the body. This is the id
+    /// itself if the states are numbered consecutively from 0, as they usually are.
+    /// Otherwise, it is looked up in an index of all states that is built by the first such
+    /// call.
+    pub(crate) fn position(&self, id: Id) -> Option<usize> {
+        match self.states.get(id as usize) {
+            Some(state) if state.id() == id => Some(id as usize),
+            _ => {
+                let positions = self.positions.get_or_init(|| {
+                    let mut positions = HashMap::with_capacity(self.states.len());
+                    for (position, state) in self.states.iter().enumerate() {
+                        positions.entry(state.id()).or_insert(position);
+                    }
+                    positions
+                });
+                positions.get(&id).copied()
+            }
+        }
+    }
+
+    /// Returns the state with the given id.
+    pub fn state(&self, id: Id) -> Option<&State> {
+        self.position(id).map(|position| &self.states[position])
+    }
+
+    /// Returns the targets of all edges leaving the state with the given id, in the order of
+    /// the edges. A target is repeated if several edges lead to it.
+    pub fn successors(&self, id: Id) -> impl Iterator<Item = Id> + '_ {
+        self.state(id)
+            .into_iter()
+            .flat_map(|state| state.edges())
+            .flat_map(|edge| edge.state_conjunction().0.iter().copied())
+    }
+
+    /// Returns the sorted ids of the states that have an edge to the state with the given
+    /// id. The reverse index that this is looked up in is built by the first call.
+    pub fn predecessors(&self, id: Id) -> &[Id] {
+        let predecessors = self.predecessors.get_or_init(|| {
+            let mut predecessors = vec![vec![]; self.states.len()];
+            for (source, edge) in self.edges() {
+                for target in &edge.state_conjunction().0 {
+                    if let Some(position) = self.position(*target) {
+                        predecessors[position].push(source);
+                    }
+                }
+            }
+            for sources in &mut predecessors {
+                sources.sort_unstable();
+                sources.dedup();
+            }
+            predecessors
+        });
+        self.position(id)
+            .map_or(&[], |position| &predecessors[position])
+    }
+
+    /// Iterates over all edges together with the id of the state they leave.
+    pub fn edges(&self) -> impl Iterator<Item = (Id, &Edge)> + '_ {
+        self.states
+            .iter()
+            .flat_map(|state| state.edges().iter().map(move |edge| (state.id(), edge)))
+    }
+
+    /// Constructs a body parser that additionally returns the spans of all states and edges.
+    /// If a state or edge cannot be parsed, the error is recorded and parsing resumes at the
+    /// next state or edge. A missing `--END--` is reported as well. Implicit labels refer to
+    /// `aps` atomic propositions.
+    pub(crate) fn spanned_parser(
+        aps: usize,
+    ) -> impl Parser<Token, (Self, BodySpans), Error = Simple<Token>> {
+        let state = state(aps).map(Some).recover_with(
+            skip_until([Token::Header("State".to_string()), Token::BodyEnd], |_| {
+                None
+            })
+            .skip_start(),
+        );
+        let body_end = just(Token::BodyEnd)
+            .ignored()
+            .or(end().validate(|_, span, emit| {
+                emit(Simple::expected_input_found(
+                    span,
+                    [Some(Token::BodyEnd)],
+                    None,
+                ))
+            }));
         just(Token::BodyStart)
-            .ignore_then(state().repeated())
-            .map(Body)
-            .then_ignore(just(Token::BodyEnd))
+            .ignore_then(
+                filter(|token| *token != Token::BodyEnd)
+                    .rewind()
+                    .ignore_then(state)
+                    .repeated(),
+            )
+            .then_ignore(body_end)
+            .map(|states| {
+                let mut body = Vec::with_capacity(states.len());
+                let mut state_spans = Vec::with_capacity(states.len());
+                let mut edge_spans = Vec::with_capacity(states.len());
+                for (state, span, edges) in states.into_iter().flatten() {
+                    body.push(state);
+                    state_spans.push(span);
+                    edge_spans.push(edges);
+                }
+                (Body::from(body), (state_spans, edge_spans))
+            })
     }
 }
 
 impl From<Vec<State>> for Body {
-    fn from(value: Vec<State>) -> Self {
-        Body(value)
+    fn from(states: Vec<State>) -> Self {
+        Body {
+            states,
+            positions: OnceLock::new(),
+            predecessors: OnceLock::new(),
+        }
+    }
+}
+
+impl PartialEq for Body {
+    fn eq(&self, other: &Self) -> bool {
+        self.states == other.states
+    }
+}
+
+impl Eq for Body {}
+
+impl std::fmt::Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Body").field(&self.states).finish()
     }
 }
 
@@ -228,13 +439,17 @@ impl Deref for Body {
     type Target = Vec<State>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.states
     }
 }
 
 impl DerefMut for Body {
+    /// Gives mutable access to the states, which discards the cached positions and
+    /// predecessors.
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        self.positions.take();
+        self.predecessors.take();
+        &mut self.states
     }
 }
 
@@ -242,9 +457,9 @@ impl DerefMut for Body {
 mod tests {
     use chumsky::{primitive::end, Parser, Stream};
 
-    use crate::{lexer, Edge, Label, StateConjunction, ALPHABET, VARS};
+    use crate::{lexer, AbstractLabelExpression, Edge, Label, StateConjunction};
 
-    use super::State;
+    use super::{Body, State};
 
     pub fn in_tags(input: &str) -> String {
         format!("--BODY--\n{}\n--END--", input)
@@ -265,7 +480,8 @@ mod tests {
             print!("{}", tok.0);
         }
         let len = input.chars().count();
-        let ast = Body::parser()
+        let ast = Body::spanned_parser(1)
+            .map(|(body, _)| body)
             .then_ignore(end())
             .parse(Stream::from_iter(len..len + 1, tokens.into_iter()))
             .map_err(|error_list| {
@@ -274,7 +490,7 @@ mod tests {
                     error_list.into_iter().map(|err| err.map(|c| c.to_string())),
                 )
             })?;
-        Ok(ast.0)
+        Ok(ast.to_vec())
     }
 
     #[test]
@@ -283,12 +499,15 @@ mod tests {
         [0 & !1] 0 {0}
         [1] 1 {0}"#;
         let t0 = Edge::from_parts(
-            Label(ALPHABET.mk_var(VARS[0]).and(&ALPHABET.mk_not_var(VARS[1]))),
+            Label(AbstractLabelExpression::Conjunction(vec![
+                AbstractLabelExpression::Integer(0),
+                AbstractLabelExpression::Negated(Box::new(AbstractLabelExpression::Integer(1))),
+            ])),
             StateConjunction(vec![0]),
             crate::AcceptanceSignature(vec![0]),
         );
         let t1 = Edge::from_parts(
-            Label(ALPHABET.mk_var(VARS[1])),
+            Label(AbstractLabelExpression::Integer(1)),
             StateConjunction(vec![1]),
             crate::AcceptanceSignature(vec![0]),
         );
@@ -303,7 +522,7 @@ mod tests {
             [t] 1 {1}
         "#;
         let t0 = Edge::from_parts(
-            Label(ALPHABET.mk_true()),
+            Label(AbstractLabelExpression::Boolean(true)),
             StateConjunction(vec![1]),
             crate::AcceptanceSignature(vec![1]),
         );
@@ -311,10 +530,123 @@ mod tests {
         assert_eq!(process_body(&in_tags(hoa)), Ok(vec![q0]));
     }
 
+    #[test]
+    fn implicit_labels() {
+        let hoa = "State: 0\n1 {0}\n0 & 1\nState: 1";
+        let negated =
+            AbstractLabelExpression::Negated(Box::new(AbstractLabelExpression::Integer(0)));
+        let t0 = Edge::from_parts(
+            Label(negated),
+            StateConjunction(vec![1]),
+            crate::AcceptanceSignature(vec![0]),
+        );
+        let t1 = Edge::from_parts(
+            Label(AbstractLabelExpression::Integer(0)),
+            StateConjunction(vec![0, 1]),
+            crate::AcceptanceSignature(vec![]),
+        );
+        let q0 = State::from_parts(0, None, vec![t0, t1]);
+        let q1 = State::from_parts(1, None, vec![]);
+        assert_eq!(process_body(&in_tags(hoa)), Ok(vec![q0, q1]));
+
+        // one edge per valuation is required and labels cannot be mixed
+        assert!(process_body(&in_tags("State: 0\n1")).is_err());
+        assert!(process_body(&in_tags("State: 0\n1\n[0] 1")).is_err());
+
+        // the edges of a labelled state are not labelled implicitly
+        let Err(crate::FromHoaError::ParserError(errors)) = crate::input::from_hoa(
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAcceptance: 0 t\n--BODY--\n\
+             State: [0] 0\n0\n--END--",
+        ) else {
+            panic!("a labelled state is accepted");
+        };
+        assert_eq!(
+            errors[0].message,
+            "State 0 has a label and edges without labels, labelled states are not supported"
+        );
+    }
+
     #[test]
     fn no_transition_state() {
         let hoa = r#"State: 1"#;
         let q0 = State::from_parts(1, None, vec![]);
         assert_eq!(process_body(&in_tags(hoa)), Ok(vec![q0]));
     }
+
+    #[test]
+    fn lookup_and_adjacency() {
+        let aut = crate::input::from_hoa(
+            r#"HOA: v1
+States: 4
+Start: 0
+AP: 1 "a"
+Acceptance: 0 t
+--BODY--
+State: 0
+[0] 1
+[!0] 2 & 3
+State: 1
+[t] 1
+[t] 0
+State: 2
+[0] 1
+State: 3
+--END--"#,
+        )
+        .unwrap();
+        let body = aut.body();
+        assert_eq!(body.state(2).map(State::id), Some(2));
+        assert!(body.state(4).is_none());
+        assert_eq!(body.successors(0).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(body.successors(3).count(), 0);
+        assert_eq!(body.predecessors(1), [0, 1, 2]);
+        assert_eq!(body.predecessors(3), [0]);
+        assert!(body.predecessors(7).is_empty());
+        let edges = body
+            .edges()
+            .map(|(source, edge)| (source, edge.state_conjunction().0.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                (0, vec![1]),
+                (0, vec![2, 3]),
+                (1, vec![1]),
+                (1, vec![0]),
+                (2, vec![1])
+            ]
+        );
+    }
+
+    #[test]
+    fn sparse_ids_and_modification() {
+        let edge = |target| {
+            Edge::from_parts(
+                Label(AbstractLabelExpression::Boolean(true)),
+                StateConjunction(vec![target]),
+                crate::AcceptanceSignature(vec![]),
+            )
+        };
+        let mut body = Body::from(vec![
+            State::from_parts(3, None, vec![edge(10)]),
+            State::from_parts(10, None, vec![edge(3)]),
+        ]);
+        assert_eq!(body.state(10).map(State::id), Some(10));
+        assert!(body.state(1).is_none());
+        assert_eq!(body.predecessors(3), [10]);
+
+        body.push(State::from_parts(1, None, vec![edge(3)]));
+        assert_eq!(body.state(1).map(State::id), Some(1));
+        assert_eq!(body.predecessors(3), [1, 10]);
+
+        // the index of positions follows a reordering
+        body.reverse();
+        assert_eq!(body.state(10).map(State::id), Some(10));
+        assert_eq!(body.state(3).map(State::id), Some(3));
+        assert_eq!(body.predecessors(3), [1, 10]);
+        body.retain(|state| state.id() != 1);
+        assert!(body.state(1).is_none());
+        assert_eq!(body.state(3).map(State::id), Some(3));
+        assert_eq!(body.predecessors(3), [10]);
+    }
 }
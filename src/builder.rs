@@ -0,0 +1,334 @@
+use itertools::Itertools;
+
+use crate::{
+    error::ParseError,
+    input::{self, max_ap},
+    lexer::Token,
+    AbstractLabelExpression, AcceptanceCondition, AcceptanceInfo, AcceptanceName,
+    AcceptanceSignature, Body, Edge, Header, HeaderItem, HoaAutomaton, HoaBool, Id, Label,
+    Property, State, StateConjunction,
+};
+
+/// Constructs a [`HoaAutomaton`] step by step. States are numbered in the order in which they
+/// are added, atomic propositions are registered by name when they first occur in a label
+/// and the `States:`, `AP:` and `Acceptance:` headers are filled in by [`Self::build`].
+#[derive(Debug, Clone, Default)]
+pub struct AutomatonBuilder {
+    name: Option<String>,
+    tool: Option<(String, Option<String>)>,
+    aps: Vec<String>,
+    start: Vec<Vec<Id>>,
+    acceptance: Option<AcceptanceCondition>,
+    acceptance_name: Option<(AcceptanceName, Vec<AcceptanceInfo>)>,
+    properties: Vec<Property>,
+    states: Vec<(Option<String>, Vec<Edge>)>,
+    errors: Vec<String>,
+}
+
+impl AutomatonBuilder {
+    /// Creates a builder for an automaton without states and atomic propositions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the automaton.
+    pub fn name(&mut self, name: impl ToString) -> &mut Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Sets the tool that produced the automaton and optionally its version.
+    pub fn tool(&mut self, tool: impl ToString, version: Option<&str>) -> &mut Self {
+        self.tool = Some((tool.to_string(), version.map(str::to_string)));
+        self
+    }
+
+    /// Returns the index of the atomic proposition with the given name, registering it if
+    /// it is not yet known.
+    pub fn ap(&mut self, name: &str) -> Id {
+        match self.aps.iter().position(|ap| ap == name) {
+            Some(index) => index as Id,
+            None => {
+                self.aps.push(name.to_string());
+                (self.aps.len() - 1) as Id
+            }
+        }
+    }
+
+    /// Adds a state and returns its id.
+    pub fn state(&mut self) -> Id {
+        self.states.push((None, vec![]));
+        (self.states.len() - 1) as Id
+    }
+
+    /// Adds a state that is annotated with the given name and returns its id.
+    pub fn named_state(&mut self, name: impl ToString) -> Id {
+        let id = self.state();
+        self.states[id as usize].0 = Some(name.to_string());
+        id
+    }
+
+    /// Makes the given state initial.
+    pub fn start(&mut self, state: Id) -> &mut Self {
+        self.universal_start([state])
+    }
+
+    /// Adds a conjunction of initial states, which makes the automaton alternating if it
+    /// contains more than one state.
+    pub fn universal_start(&mut self, states: impl IntoIterator<Item = Id>) -> &mut Self {
+        self.start.push(states.into_iter().collect());
+        self
+    }
+
+    /// Sets the acceptance condition, the number of acceptance sets is the largest set that
+    /// occurs in the condition or on an edge plus one. If no condition is set, every run is
+    /// accepting.
+    pub fn acceptance(&mut self, condition: AcceptanceCondition) -> &mut Self {
+        self.acceptance = Some(condition);
+        self
+    }
+
+    /// Sets the name of the acceptance condition, the `acc-name:` header.
+    pub fn acceptance_name(
+        &mut self,
+        name: AcceptanceName,
+        info: impl IntoIterator<Item = AcceptanceInfo>,
+    ) -> &mut Self {
+        self.acceptance_name = Some((name, info.into_iter().collect()));
+        self
+    }
+
+    /// Declares a property of the automaton.
+    pub fn property(&mut self, property: Property) -> &mut Self {
+        self.properties.push(property);
+        self
+    }
+
+    /// Adds an edge from `from` to `to` that belongs to the given acceptance sets. The label
+    /// is a boolean expression in HOA syntax over the constants `t` and `f` and atomic
+    /// propositions, which are referred to by name, either as an identifier or as a quoted
+    /// string, or by index. Errors in the label are reported by [`Self::build`].
+    pub fn edge(
+        &mut self,
+        from: Id,
+        label: &str,
+        to: Id,
+        marks: impl IntoIterator<Item = Id>,
+    ) -> &mut Self {
+        self.universal_edge(from, label, [to], marks)
+    }
+
+    /// Like [`Self::edge`], but the edge leads to a conjunction of states, which makes the
+    /// automaton alternating if there is more than one.
+    pub fn universal_edge(
+        &mut self,
+        from: Id,
+        label: &str,
+        to: impl IntoIterator<Item = Id>,
+        marks: impl IntoIterator<Item = Id>,
+    ) -> &mut Self {
+        if from as usize >= self.states.len() {
+            self.errors.push(format!(
+                "The edge {label:?} leaves state {from}, which does not exist"
+            ));
+            return self;
+        }
+        match self.label(label) {
+            Ok(expression) => self.states[from as usize].1.push(Edge::from_parts(
+                Label(expression),
+                StateConjunction(to.into_iter().collect()),
+                AcceptanceSignature(marks.into_iter().collect()),
+            )),
+            Err(errors) => self.errors.extend(
+                errors
+                    .into_iter()
+                    .map(|error| format!("In the label {label:?}: {error}")),
+            ),
+        }
+        self
+    }
+
+    /// Parses a label, registering the atomic propositions it refers to by name.
+    fn label(&mut self, label: &str) -> Result<AbstractLabelExpression, Vec<ParseError>> {
+        input::parse_label(label, |(token, span)| {
+            let name = match &token {
+                Token::Identifier(name) if name != "t" && name != "f" => name.as_str(),
+                Token::Text(name) => name.as_str(),
+                Token::Fin => "Fin",
+                Token::Inf => "Inf",
+                _ => return (token, span),
+            };
+            (Token::Int(self.ap(name).to_string()), span)
+        })
+    }
+
+    /// Assembles the automaton. Fails with a description of every problem, like a label that
+    /// cannot be parsed or an edge to a state that does not exist.
+    pub fn build(&self) -> Result<HoaAutomaton, String> {
+        let mut errors = self.errors.clone();
+        let states = self.states.len();
+        let unknown_state = |state: &Id| *state as usize >= states;
+        for state in self
+            .start
+            .iter()
+            .flatten()
+            .filter(|state| unknown_state(state))
+        {
+            errors.push(format!("The initial state {state} does not exist"));
+        }
+        let edges = self
+            .states
+            .iter()
+            .enumerate()
+            .flat_map(|(from, (_, edges))| edges.iter().map(move |edge| (from, edge)));
+        let mut sets = self
+            .acceptance
+            .as_ref()
+            .map_or(0, AcceptanceCondition::set_count);
+        for (from, edge) in edges {
+            for state in edge
+                .state_conjunction()
+                .0
+                .iter()
+                .filter(|q| unknown_state(q))
+            {
+                errors.push(format!(
+                    "An edge of state {from} leads to {state}, which does not exist"
+                ));
+            }
+            if let Some(ap) = max_ap(&edge.label().0).filter(|ap| *ap as usize >= self.aps.len()) {
+                errors.push(format!(
+                    "An edge of state {from} refers to the atomic proposition {ap}, which is not declared"
+                ));
+            }
+            sets = sets.max(
+                edge.acceptance_signature()
+                    .iter()
+                    .map(|set| set + 1)
+                    .max()
+                    .unwrap_or(0),
+            );
+        }
+
+        let mut header = vec![HeaderItem::v1()];
+        header.extend(self.name.clone().map(HeaderItem::Name));
+        header.extend(
+            self.tool
+                .clone()
+                .map(|(tool, version)| HeaderItem::Tool(tool, version)),
+        );
+        header.push(HeaderItem::States(states as Id));
+        header.extend(
+            self.start
+                .iter()
+                .map(|conjunction| HeaderItem::Start(StateConjunction(conjunction.clone()))),
+        );
+        header.push(HeaderItem::AP(self.aps.clone()));
+        header.extend(
+            self.acceptance_name
+                .clone()
+                .map(|(name, info)| HeaderItem::AcceptanceName(name, info)),
+        );
+        header.push(HeaderItem::Acceptance(
+            sets,
+            self.acceptance
+                .clone()
+                .unwrap_or(AcceptanceCondition::Boolean(HoaBool(true))),
+        ));
+        if !self.properties.is_empty() {
+            header.push(HeaderItem::Properties(self.properties.clone()));
+        }
+        let body = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(id, (name, edges))| State::from_parts(id as Id, name.clone(), edges.clone()))
+            .collect_vec();
+        let aut = HoaAutomaton::from_parts(Header::from_vec(header), Body::from(body));
+        if let Err(error) = aut.verify() {
+            errors.push(error);
+        }
+        match errors.is_empty() {
+            true => Ok(aut),
+            false => Err(errors.join("\n")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AutomatonBuilder;
+    use crate::{
+        input::from_hoa, output::to_hoa, AcceptanceCondition, AcceptanceName, HoaAutomaton,
+    };
+
+    #[test]
+    fn matches_parsed_automaton() {
+        let parsed = from_hoa(
+            r#"HOA: v1
+States: 3
+Start: 0
+AP: 2 "a" "b c"
+acc-name: Buchi
+Acceptance: 1 Inf(0)
+--BODY--
+State: 0 "init"
+[0 & !1] 1 {0}
+[!0 | 1] 2
+State: 1
+[t] 1
+State: 2
+[1] 0 & 2
+--END--"#,
+        )
+        .unwrap();
+
+        let mut builder = AutomatonBuilder::new();
+        let q0 = builder.named_state("init");
+        let (q1, q2) = (builder.state(), builder.state());
+        builder
+            .start(q0)
+            .acceptance_name(AcceptanceName::Buchi, [])
+            .acceptance(AcceptanceCondition::buchi())
+            .edge(q0, r#"a & !"b c""#, q1, [0])
+            .edge(q0, r#"!a | "b c""#, q2, [])
+            .edge(q1, "t", q1, [])
+            .universal_edge(q2, "1", [q0, q2], []);
+        let built = builder.build().unwrap();
+        assert_eq!(built, parsed);
+        assert_eq!(HoaAutomaton::try_from(to_hoa(&built).as_str()), Ok(built));
+    }
+
+    #[test]
+    fn fills_headers() {
+        let mut builder = AutomatonBuilder::new();
+        let q = builder.state();
+        builder.edge(q, "Fin | (x & !y)", q, [2]);
+        let aut = builder.build().unwrap();
+        assert_eq!(aut.aps(), ["Fin", "x", "y"]);
+        assert_eq!(aut.num_states(), Some(1));
+        assert_eq!(aut.acceptance().0, 3);
+        assert!(aut.start().is_empty());
+    }
+
+    #[test]
+    fn reports_all_problems() {
+        let mut builder = AutomatonBuilder::new();
+        let q = builder.state();
+        builder
+            .start(3)
+            .edge(q, "a &", q, [])
+            .edge(q, "a $ b", q, [])
+            .edge(q, "a", 1, [])
+            .edge(5, "a", q, [])
+            .edge(q, "7", q, []);
+        let errors = builder.build().unwrap_err();
+        let errors = errors.lines().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 6, "{errors:?}");
+        assert_eq!(
+            errors[0],
+            "In the label \"a &\": 1:4: Unexpected end of input, expected !, ("
+        );
+        assert_eq!(errors[3], "The initial state 3 does not exist");
+    }
+}
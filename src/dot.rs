@@ -0,0 +1,262 @@
+//! Export of automata to the [DOT language](https://graphviz.org/doc/info/lang.html) of
+//! Graphviz. The rendering follows Spot: acceptance sets are drawn as coloured bullets and
+//! initial states are marked by an arrow that comes from nowhere.
+
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::{
+    output::{quoted, AcceptancePlacement},
+    AcceptanceAtom, AcceptanceCondition, AcceptanceSignature, HeaderItem, HoaAutomaton, Id,
+    StateConjunction,
+};
+
+/// The colours of the acceptance sets, the i-th set is drawn in the i-th colour modulo
+/// the number of colours.
+const COLORS: [&str; 10] = [
+    "#1F78B4", "#FF4DA0", "#FF7F00", "#6A3D9A", "#33A02C", "#E31A1C", "#FFBC00", "#B15928",
+    "#00A4A6", "#8C8C8C",
+];
+
+/// Writes the automaton in the DOT language using a [`DotWriter`] with default options.
+pub fn to_dot(aut: &HoaAutomaton) -> String {
+    let mut writer = DotWriter::new(vec![]);
+    writer.write(aut).expect("writing to a vector cannot fail");
+    String::from_utf8(writer.into_inner()).expect("the output is valid UTF-8")
+}
+
+/// Writes automata in the DOT language to an [`std::io::Write`]. States are named by their
+/// comment, or their id if they have none, and labels refer to atomic propositions by name.
+/// By default, acceptance sets are drawn on the edges.
+#[derive(Debug)]
+pub struct DotWriter<W> {
+    out: W,
+    acceptance: AcceptancePlacement,
+}
+
+impl<W: Write> DotWriter<W> {
+    /// Creates a writer with default options.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            acceptance: AcceptancePlacement::default(),
+        }
+    }
+
+    /// Chooses where acceptance sets are drawn. With [`AcceptancePlacement::State`], they
+    /// are drawn inside the states, provided that all edges leaving each state carry the
+    /// same acceptance sets. Otherwise they are drawn on the edges.
+    pub fn acceptance(mut self, acceptance: AcceptancePlacement) -> Self {
+        self.acceptance = acceptance;
+        self
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Writes a single automaton as a `digraph`. Fails if the underlying writer fails.
+    pub fn write(&mut self, aut: &HoaAutomaton) -> std::io::Result<()> {
+        let state_acceptance = self.acceptance == AcceptancePlacement::State
+            && aut.body().iter().all(|state| {
+                state
+                    .edges()
+                    .iter()
+                    .map(|edge| edge.acceptance_signature())
+                    .all_equal()
+            });
+        let name = aut.header().iter().find_map(|item| match item {
+            HeaderItem::Name(name) => Some(name.as_str()),
+            _ => None,
+        });
+
+        let mut lines = vec![];
+        lines.push(format!("digraph {} {{", quoted(name.unwrap_or(""))));
+        lines.push("  rankdir=LR".to_string());
+        let mut title = name.map(escape).into_iter().collect_vec();
+        title.push(acceptance_html(&aut.acceptance().1));
+        if let Some((name, info)) = aut.acceptance_name() {
+            let info = info.iter().map(|info| format!(" {info}")).join("");
+            title.push(escape(&format!("[{name}{info}]")));
+        }
+        lines.push(format!("  label=<{}>", title.join("<br/>")));
+        lines.push("  labelloc=\"t\"".to_string());
+        lines.push("  node [shape=\"circle\"]".to_string());
+
+        let mut hyperedges = 0;
+        let mut hyperedge = |lines: &mut Vec<String>,
+                             source: String,
+                             attributes: String,
+                             targets: &StateConjunction| {
+            match targets.get_singleton() {
+                Some(target) => lines.push(format!("  {source} -> {target}{attributes}")),
+                None => {
+                    let point = format!("U{hyperedges}");
+                    hyperedges += 1;
+                    lines.push(format!("  {point} [label=\"\", shape=point, width=0.05]"));
+                    lines.push(format!("  {source} -> {point}{attributes}"));
+                    for target in &targets.0 {
+                        lines.push(format!("  {point} -> {target}"));
+                    }
+                }
+            }
+        };
+
+        for (i, start) in aut.start().into_iter().enumerate() {
+            lines.push(format!("  I{i} [label=\"\", style=invis, width=0]"));
+            hyperedge(&mut lines, format!("I{i}"), String::new(), start);
+        }
+
+        let aps = aut.aps();
+        for state in aut.body() {
+            let mut label = match state.comment() {
+                Some(comment) => escape(comment),
+                None => state.id().to_string(),
+            };
+            match state.edges().first() {
+                Some(edge) if state_acceptance && !edge.acceptance_signature().is_empty() => {
+                    label.push_str("<br/>");
+                    label.push_str(&marks_html(edge.acceptance_signature()));
+                }
+                _ => {}
+            }
+            lines.push(format!("  {} [label=<{label}>]", state.id()));
+            for edge in state.edges() {
+                let mut label = escape(&edge.label().with_names(aps).to_string());
+                if !state_acceptance && !edge.acceptance_signature().is_empty() {
+                    label.push_str("<br/>");
+                    label.push_str(&marks_html(edge.acceptance_signature()));
+                }
+                hyperedge(
+                    &mut lines,
+                    state.id().to_string(),
+                    format!(" [label=<{label}>]"),
+                    edge.state_conjunction(),
+                );
+            }
+        }
+        lines.push("}".to_string());
+        writeln!(self.out, "{}", lines.join("\n"))
+    }
+}
+
+/// Escapes the characters that have a special meaning in HTML-like labels.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Draws an acceptance set as a coloured bullet that contains its number, sets beyond the
+/// numbered bullets are drawn as their number in parentheses.
+fn bullet(set: Id) -> String {
+    let symbol = match set {
+        0 => Some('⓿'),
+        1..=10 => char::from_u32(0x2775 + set),
+        11..=20 => char::from_u32(0x24EB + set - 11),
+        _ => None,
+    }
+    .map_or_else(|| format!("({set})"), String::from);
+    let color = COLORS[set as usize % COLORS.len()];
+    format!("<font color=\"{color}\">{symbol}</font>")
+}
+
+/// Draws the acceptance sets of an edge or state.
+fn marks_html(marks: &AcceptanceSignature) -> String {
+    marks.iter().map(|set| bullet(*set)).join("")
+}
+
+/// Writes an acceptance condition like its [`std::fmt::Display`] implementation does, but
+/// with the acceptance sets drawn as bullets.
+fn acceptance_html(condition: &AcceptanceCondition) -> String {
+    let atom = |atom: &AcceptanceAtom| match atom {
+        AcceptanceAtom::Positive(set) => bullet(*set),
+        AcceptanceAtom::Negative(set) => format!("!{}", bullet(*set)),
+    };
+    match condition {
+        AcceptanceCondition::Fin(set) => format!("Fin({})", atom(set)),
+        AcceptanceCondition::Inf(set) => format!("Inf({})", atom(set)),
+        AcceptanceCondition::And(left, right) => {
+            format!(
+                "({} &amp; {})",
+                acceptance_html(left),
+                acceptance_html(right)
+            )
+        }
+        AcceptanceCondition::Or(left, right) => {
+            format!("({} | {})", acceptance_html(left), acceptance_html(right))
+        }
+        AcceptanceCondition::Boolean(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_dot, DotWriter};
+    use crate::{output::AcceptancePlacement, HoaAutomaton};
+
+    const INPUT: &str = r#"HOA: v1
+name: "a \"quoted\" name"
+States: 3
+Start: 0
+Start: 1 & 2
+AP: 2 "a" "b c"
+acc-name: generalized-Buchi 2
+Acceptance: 2 Inf(0) & Inf(1)
+--BODY--
+State: 0 "init"
+[0 & !1] 1 {0}
+[!0] 0 {0}
+State: 1
+[t] 1 & 2 {1}
+State: 2
+[1] 2 {0 1}
+--END--"#;
+
+    #[test]
+    fn transition_marks_and_hyperedges() {
+        let aut = HoaAutomaton::try_from(INPUT).unwrap();
+        let dot = to_dot(&aut);
+        assert!(dot.starts_with("digraph \"a \\\"quoted\\\" name\" {\n  rankdir=LR\n"));
+        assert!(dot.contains(
+            "  label=<a &quot;quoted&quot; name<br/>(Inf(<font color=\"#1F78B4\">⓿</font>) &amp; \
+             Inf(<font color=\"#FF4DA0\">❶</font>))<br/>[generalized-Buchi 2]>\n"
+        ));
+        assert!(dot.contains("  I0 [label=\"\", style=invis, width=0]\n  I0 -> 0\n"));
+        assert!(dot.contains(
+            "  I1 [label=\"\", style=invis, width=0]\n  U0 [label=\"\", shape=point, width=0.05]\n  \
+             I1 -> U0\n  U0 -> 1\n  U0 -> 2\n"
+        ));
+        assert!(dot.contains("  0 [label=<init>]\n"));
+        assert!(dot.contains(
+            "  0 -> 1 [label=<a &amp; !&quot;b c&quot;<br/><font color=\"#1F78B4\">⓿</font>>]\n"
+        ));
+        assert!(dot.contains("  1 -> U1 [label=<t<br/><font color=\"#FF4DA0\">❶</font>>]\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn state_marks() {
+        let aut = HoaAutomaton::try_from(INPUT).unwrap();
+        let mut writer = DotWriter::new(vec![]).acceptance(AcceptancePlacement::State);
+        writer.write(&aut).unwrap();
+        let dot = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(dot.contains("  0 [label=<init<br/><font color=\"#1F78B4\">⓿</font>>]\n"));
+        assert!(dot.contains("  0 -> 0 [label=<!a>]\n"));
+        assert!(dot.contains(
+            "  2 [label=<2<br/><font color=\"#1F78B4\">⓿</font><font color=\"#FF4DA0\">❶</font>>]\n"
+        ));
+
+        // marks that differ between the edges of a state stay on the edges
+        let mut aut = aut;
+        aut.body_mut()[0].edges_mut()[1] = aut.body()[2].edges()[0].clone();
+        let mut writer = DotWriter::new(vec![]).acceptance(AcceptancePlacement::State);
+        writer.write(&aut).unwrap();
+        let dot = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(dot.contains("  0 [label=<init>]\n"));
+    }
+}
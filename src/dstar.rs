@@ -0,0 +1,342 @@
+//! Import of deterministic Rabin and Streett automata in the explicit text format of
+//! [ltl2dstar](https://www.ltl2dstar.de/), version 2:
+//!
+//! ```text
+//! DRA v2 explicit
+//! Comment: "optional"
+//! States: 2
+//! Acceptance-Pairs: 1
+//! Start: 0
+//! AP: 1 "a"
+//! ---
+//! State: 0
+//! Acc-Sig: +0
+//! 1
+//! 0
+//! State: 1
+//! Acc-Sig: -0
+//! 1
+//! 1
+//! ```
+//!
+//! Each state lists one successor per valuation of the atomic propositions, where the
+//! valuations are ordered as binary numbers with the first proposition as the least
+//! significant bit. The acceptance signature of a state says to which sets of the pairs
+//! it belongs, `-i` for the set that has to be visited finitely often in a Rabin pair and
+//! `+i` for the other one.
+
+use itertools::Itertools;
+
+use crate::{
+    body::valuation_label, input::MAX_ACCEPTANCE_SETS, AcceptanceCondition, AcceptanceInfo,
+    AcceptanceName, AcceptanceSignature, Body, Edge, Header, HeaderItem, HoaAutomaton, Id,
+    Property, State, StateConjunction,
+};
+
+/// Reads a `DRA v2 explicit` or `DSA v2 explicit` automaton. A Rabin automaton with `n`
+/// pairs gets the condition `(Fin(0) & Inf(1)) | ... | (Fin(2n - 2) & Inf(2n - 1))` and
+/// a Streett automaton `(Fin(0) | Inf(1)) & ... & (Fin(2n - 2) | Inf(2n - 1))`, the state
+/// is in set `2i` if its signature contains `-i` and in set `2i + 1` if it contains `+i`.
+/// The edges are labelled explicitly with the valuation they belong to.
+pub fn from_dstar(input: &str) -> Result<HoaAutomaton, String> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let rabin = match lines.next() {
+        Some((_, "DRA v2 explicit")) => true,
+        Some((_, "DSA v2 explicit")) => false,
+        Some((line, first)) => {
+            return Err(format!(
+                "line {line}: Expected \"DRA v2 explicit\" or \"DSA v2 explicit\", found {first:?}"
+            ))
+        }
+        None => return Err("The input is empty".to_string()),
+    };
+
+    let (mut states, mut pairs, mut start, mut aps) = (None, None, None, None);
+    loop {
+        let Some((line, content)) = lines.next() else {
+            return Err("The header is not terminated by ---".to_string());
+        };
+        if content == "---" {
+            break;
+        }
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<Id>()
+                .map_err(|_| format!("line {line}: Expected a number, found {value:?}"))
+        };
+        match content.split_once(':') {
+            Some(("Comment", _)) => {}
+            Some(("States", value)) => states = Some(number(value)?),
+            Some(("Acceptance-Pairs", value)) => pairs = Some(number(value)?),
+            Some(("Start", value)) => start = Some(number(value)?),
+            Some(("AP", value)) => {
+                let value = value.trim_start();
+                let (count, names) = value.split_once(' ').unwrap_or((value, ""));
+                let names = quoted_strings(names)
+                    .ok_or_else(|| format!("line {line}: Malformed names of propositions"))?;
+                if names.len() != number(count)? as usize {
+                    return Err(format!(
+                        "line {line}: Expected {count} propositions, found {}",
+                        names.len()
+                    ));
+                }
+                aps = Some(names);
+            }
+            _ => return Err(format!("line {line}: Unknown header {content:?}")),
+        }
+    }
+    let missing = |header: &str| format!("The {header} header is missing");
+    let states = states.ok_or_else(|| missing("States"))?;
+    let pairs = pairs.ok_or_else(|| missing("Acceptance-Pairs"))?;
+    if 2 * pairs as usize > MAX_ACCEPTANCE_SETS {
+        return Err(format!(
+            "More than {} acceptance pairs are not supported",
+            MAX_ACCEPTANCE_SETS / 2
+        ));
+    }
+    let start = start.ok_or_else(|| missing("Start"))?;
+    let aps = aps.ok_or_else(|| missing("AP"))?;
+    let valuations = u32::try_from(aps.len())
+        .ok()
+        .and_then(|count| 1usize.checked_shl(count))
+        .filter(|valuations| *valuations <= input.len())
+        .ok_or("There are too many atomic propositions")?;
+    let labels = (0..valuations).map(|valuation| valuation_label(valuation, aps.len()));
+    let labels = labels.collect_vec();
+
+    let mut body: Vec<State> = vec![];
+    while let Some((line, content)) = lines.next() {
+        let (id, comment) = content
+            .strip_prefix("State:")
+            .map(|rest| {
+                let rest = rest.trim_start();
+                rest.split_once(' ').unwrap_or((rest, ""))
+            })
+            .ok_or_else(|| format!("line {line}: Expected a state, found {content:?}"))?;
+        let id = id
+            .parse::<Id>()
+            .ok()
+            .filter(|id| *id < states)
+            .ok_or_else(|| format!("line {line}: Invalid state id {id:?}"))?;
+        if body.iter().any(|state| state.id() == id) {
+            return Err(format!("line {line}: State {id} is listed twice"));
+        }
+        let comment = match quoted_strings(comment).as_deref() {
+            Some([]) => None,
+            Some([comment]) => Some(comment.clone()),
+            _ => return Err(format!("line {line}: Malformed name of state {id}")),
+        };
+
+        let Some((line, signature)) = lines.next() else {
+            return Err(format!("The acceptance signature of state {id} is missing"));
+        };
+        let signature = signature
+            .strip_prefix("Acc-Sig:")
+            .ok_or_else(|| format!("line {line}: Expected Acc-Sig, found {signature:?}"))?;
+        let marks = signature
+            .split_whitespace()
+            .map(|mark| {
+                let (offset, pair) = match mark.split_at(mark.len().min(1)) {
+                    ("-", pair) => (0, pair),
+                    ("+", pair) => (1, pair),
+                    _ => (0, ""),
+                };
+                pair.parse::<Id>()
+                    .ok()
+                    .filter(|pair| *pair < pairs)
+                    .map(|pair| 2 * pair + offset)
+                    .ok_or_else(|| format!("line {line}: Invalid acceptance signature {mark:?}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let marks = AcceptanceSignature(marks.into_iter().sorted().dedup().collect());
+
+        let mut edges = Vec::with_capacity(valuations);
+        for label in &labels {
+            let Some((line, target)) = lines.next() else {
+                return Err(format!("State {id} has fewer than {valuations} successors"));
+            };
+            let target = target
+                .parse::<Id>()
+                .ok()
+                .filter(|target| *target < states)
+                .ok_or_else(|| format!("line {line}: Invalid successor {target:?}"))?;
+            edges.push(Edge::from_parts(
+                label.clone(),
+                StateConjunction::singleton(target),
+                marks.clone(),
+            ));
+        }
+        body.push(State::from_parts(id, comment, edges));
+    }
+    if body.len() != states as usize {
+        return Err(format!("Expected {states} states, found {}", body.len()));
+    }
+
+    let pair_conditions = (0..pairs).map(|i| {
+        let (fin, inf) = (
+            AcceptanceCondition::id_fin(2 * i),
+            AcceptanceCondition::id_inf(2 * i + 1),
+        );
+        match rabin {
+            true => fin.and(inf),
+            false => fin.or(inf),
+        }
+    });
+    let condition = match rabin {
+        true => AcceptanceCondition::any(pair_conditions.collect()),
+        false => AcceptanceCondition::all(pair_conditions.collect()),
+    };
+    let name = match rabin {
+        true => AcceptanceName::Rabin,
+        false => AcceptanceName::Streett,
+    };
+    let header = vec![
+        HeaderItem::v1(),
+        HeaderItem::States(states),
+        HeaderItem::Start(StateConjunction::singleton(start)),
+        HeaderItem::AP(aps),
+        HeaderItem::AcceptanceName(name, vec![AcceptanceInfo::Int(pairs)]),
+        HeaderItem::Acceptance(2 * pairs, condition),
+        HeaderItem::Properties(vec![
+            Property::TransLabels,
+            Property::ExplicitLabels,
+            Property::StateAcceptance,
+            Property::Deterministic,
+            Property::Complete,
+        ]),
+    ];
+    let aut = HoaAutomaton::from_parts(Header::from_vec(header), Body::from(body));
+    aut.verify()?;
+    Ok(aut)
+}
+
+/// Splits a whitespace separated list of quoted strings, which may contain escaped quotes
+/// and backslashes. Returns `None` if the list is malformed.
+fn quoted_strings(input: &str) -> Option<Vec<String>> {
+    let mut strings = vec![];
+    let mut chars = input.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => string.push(chars.next()?),
+                        c => string.push(c),
+                    }
+                }
+                strings.push(string);
+            }
+            c if c.is_whitespace() => {}
+            _ => return None,
+        }
+    }
+    Some(strings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_dstar;
+    use crate::HoaAutomaton;
+
+    #[test]
+    fn rabin() {
+        let aut = from_dstar(
+            r#"DRA v2 explicit
+Comment: "Union{Safra[NBA=2]}"
+States: 2
+Acceptance-Pairs: 2
+Start: 1
+AP: 2 "a" "b c"
+---
+State: 0
+Acc-Sig: +0 -1
+0
+1
+0
+1
+State: 1 "initial"
+Acc-Sig:
+1
+1
+0
+0
+"#,
+        )
+        .unwrap();
+        let expected = HoaAutomaton::try_from(
+            r#"HOA: v1
+States: 2
+Start: 1
+AP: 2 "a" "b c"
+acc-name: Rabin 2
+Acceptance: 4 (Fin(0) & Inf(1)) | (Fin(2) & Inf(3))
+properties: trans-labels explicit-labels state-acc deterministic complete
+--BODY--
+State: 0
+[!0 & !1] 0 {1 2}
+[0 & !1] 1 {1 2}
+[!0 & 1] 0 {1 2}
+[0 & 1] 1 {1 2}
+State: 1 "initial"
+[!0 & !1] 1
+[0 & !1] 1
+[!0 & 1] 0
+[0 & 1] 0
+--END--"#,
+        )
+        .unwrap();
+        assert_eq!(aut, expected);
+    }
+
+    #[test]
+    fn streett_without_propositions() {
+        let aut = from_dstar(
+            "DSA v2 explicit\nStates: 1\nAcceptance-Pairs: 1\nStart: 0\nAP: 0\n---\n\
+             State: 0\nAcc-Sig: -0\n0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            crate::output::to_hoa(&aut),
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 0\nacc-name: Streett 1\n\
+             Acceptance: 2 (Fin(0) | Inf(1))\n\
+             properties: trans-labels explicit-labels state-acc deterministic complete\n\
+             --BODY--\nState: 0\n[t] 0 {0}\n--END--\n"
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let header =
+            "DRA v2 explicit\nStates: 1\nAcceptance-Pairs: 1\nStart: 0\nAP: 1 \"a\"\n---\n";
+        for (body, error) in [
+            (
+                "State: 0\nAcc-Sig: +1\n0\n0",
+                "line 8: Invalid acceptance signature \"+1\"",
+            ),
+            (
+                "State: 0\nAcc-Sig:\n0",
+                "State 0 has fewer than 2 successors",
+            ),
+            (
+                "State: 0\nAcc-Sig:\n0\n1",
+                "line 10: Invalid successor \"1\"",
+            ),
+            ("", "Expected 1 states, found 0"),
+        ] {
+            assert_eq!(
+                from_dstar(&format!("{header}{body}")),
+                Err(error.to_string())
+            );
+        }
+        assert!(from_dstar("DRA v3 explicit").is_err());
+        assert!(from_dstar(&header.replace("Pairs: 1", "Pairs: 4294967295")).is_err());
+        assert!(from_dstar("DRA v2 explicit\nStates: 1\nAP: 64 \"a\"\n---\n").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use itertools::Itertools;
+
+use crate::{AcceptanceCondition, Edge, HeaderItem, HoaAutomaton, Id, State, StateConjunction};
+
+/// Editing operations that keep the `States:`, `Start:` and `Acceptance:` headers consistent
+/// with the body. The recorded [`crate::Spans`] are dropped by every edit.
+impl HoaAutomaton {
+    /// Adds an edge leaving the state `from`. Fails if `from` or a target of the edge is not
+    /// a state. The number of acceptance sets grows if the edge belongs to a set that the
+    /// `Acceptance:` header does not declare yet.
+    pub fn add_edge(&mut self, from: Id, edge: Edge) -> Result<(), String> {
+        if let Some(target) = edge.1 .0.iter().find(|q| self.body.position(**q).is_none()) {
+            return Err(format!("The target {target} of the edge is not a state"));
+        }
+        let position = self.position(from)?;
+        let sets = edge.2.iter().map(|set| set.saturating_add(1)).max();
+        self.body[position].2.push(edge);
+        if let Some(sets) = sets {
+            self.require_acceptance_sets(sets);
+        }
+        self.spans = None;
+        Ok(())
+    }
+
+    /// Removes the edge with the given index from the edges leaving `from` and returns it.
+    pub fn remove_edge(&mut self, from: Id, index: usize) -> Option<Edge> {
+        let position = self.position(from).ok()?;
+        let edges = &mut self.body[position].2;
+        if index >= edges.len() {
+            return None;
+        }
+        self.spans = None;
+        Some(edges.remove(index))
+    }
+
+    /// Removes the state with the given id together with all edges and initial conjunctions
+    /// that lead to it. The states with a larger id are renumbered to close the gap.
+    pub fn remove_state(&mut self, id: Id) -> Option<State> {
+        let position = self.position(id).ok()?;
+        let state = self.body.remove(position);
+        let rename = |conjunction: &mut StateConjunction| {
+            for q in conjunction.0.iter_mut().filter(|q| **q > id) {
+                *q -= 1;
+            }
+        };
+        for state in self.body.iter_mut() {
+            if state.0 > id {
+                state.0 -= 1;
+            }
+            state.2.retain(|edge| !edge.1 .0.contains(&id));
+            for edge in &mut state.2 {
+                rename(&mut edge.1);
+            }
+        }
+        self.header.retain(|item| match item {
+            HeaderItem::Start(conjunction) => !conjunction.0.contains(&id),
+            _ => true,
+        });
+        for item in self.header.iter_mut() {
+            if let HeaderItem::Start(conjunction) = item {
+                rename(conjunction);
+            }
+        }
+        self.update_states();
+        Some(state)
+    }
+
+    /// Makes all edges that lead to `from` lead to `to` instead. Fails if `to` is not a
+    /// state. The initial states are left unchanged.
+    pub fn redirect_edges(&mut self, from: Id, to: Id) -> Result<(), String> {
+        self.position(to)?;
+        for edge in self.body.iter_mut().flat_map(|state| state.2.iter_mut()) {
+            if edge.1 .0.contains(&from) {
+                edge.1 .0 = std::mem::take(&mut edge.1 .0)
+                    .into_iter()
+                    .map(|q| if q == from { to } else { q })
+                    .unique()
+                    .collect();
+            }
+        }
+        self.spans = None;
+        Ok(())
+    }
+
+    /// Replaces the initial states, each of the given states becomes initial on its own.
+    /// Fails if one of them is not a state.
+    pub fn set_start(&mut self, states: impl IntoIterator<Item = Id>) -> Result<(), String> {
+        let states = states.into_iter().collect_vec();
+        for state in &states {
+            self.position(*state)?;
+        }
+        let position = self
+            .header
+            .iter()
+            .position(|item| matches!(item, HeaderItem::Start(_)))
+            .unwrap_or(self.header.len().min(2));
+        self.header
+            .retain(|item| !matches!(item, HeaderItem::Start(_)));
+        let start = states
+            .into_iter()
+            .map(|state| HeaderItem::Start(StateConjunction(vec![state])));
+        self.header.splice(position..position, start);
+        self.spans = None;
+        Ok(())
+    }
+
+    /// Replaces the acceptance condition. The number of acceptance sets is the largest set
+    /// that occurs in the condition or on an edge plus one. The `acc-name:` header is
+    /// dropped, as it may no longer describe the condition.
+    pub fn set_acceptance(&mut self, condition: AcceptanceCondition) {
+        let sets = self
+            .body
+            .edges()
+            .flat_map(|(_, edge)| edge.2.iter().map(|set| set.saturating_add(1)))
+            .chain([condition.set_count()])
+            .max()
+            .unwrap_or(0);
+        self.header
+            .retain(|item| !matches!(item, HeaderItem::AcceptanceName(..)));
+        match self
+            .header
+            .iter_mut()
+            .find(|item| matches!(item, HeaderItem::Acceptance(..)))
+        {
+            Some(item) => *item = HeaderItem::Acceptance(sets, condition),
+            None => self.header.push(HeaderItem::Acceptance(sets, condition)),
+        }
+        self.spans = None;
+    }
+
+    /// Returns the position of the state with the given id in the body.
+    fn position(&self, id: Id) -> Result<usize, String> {
+        self.body
+            .position(id)
+            .ok_or_else(|| format!("There is no state {id}"))
+    }
+
+    /// Sets the `States:` header to the number of states in the body.
+    pub(crate) fn update_states(&mut self) {
+        let states = self.body.len() as Id;
+        match self.header.iter_mut().find_map(|item| match item {
+            HeaderItem::States(count) => Some(count),
+            _ => None,
+        }) {
+            Some(count) => *count = states,
+            None => {
+                let position = self.header.len().min(1);
+                self.header.insert(position, HeaderItem::States(states));
+            }
+        }
+        self.spans = None;
+    }
+
+    /// Makes sure that the `Acceptance:` header declares at least the given number of sets.
+    fn require_acceptance_sets(&mut self, sets: Id) {
+        for item in self.header.iter_mut() {
+            if let HeaderItem::Acceptance(count, _) = item {
+                *count = sets.max(*count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        input::from_hoa, AbstractLabelExpression, AcceptanceCondition, AcceptanceSignature, Edge,
+        HoaAutomaton, Label, StateConjunction,
+    };
+
+    const INPUT: &str = r#"HOA: v1
+States: 4
+Start: 0
+Start: 1 & 3
+AP: 1 "a"
+acc-name: Buchi
+Acceptance: 1 Inf(0)
+--BODY--
+State: 0
+[0] 1 {0}
+[!0] 2
+State: 1
+[t] 2 & 3
+State: 2
+[t] 3
+State: 3
+[0] 0
+[!0] 1
+--END--"#;
+
+    fn edge(targets: Vec<u32>, marks: Vec<u32>) -> Edge {
+        Edge::from_parts(
+            Label(AbstractLabelExpression::Boolean(true)),
+            StateConjunction(targets),
+            AcceptanceSignature(marks),
+        )
+    }
+
+    #[test]
+    fn edges() {
+        let mut aut = from_hoa(INPUT).unwrap();
+        assert!(aut.add_edge(2, edge(vec![4], vec![])).is_err());
+        assert!(aut.add_edge(4, edge(vec![0], vec![])).is_err());
+        aut.add_edge(2, edge(vec![0], vec![2])).unwrap();
+        assert_eq!(aut.acceptance().0, 3);
+        assert_eq!(aut.body().successors(2).collect::<Vec<_>>(), vec![3, 0]);
+        assert_eq!(aut.remove_edge(2, 0).map(|e| e.target()), Some(Some(3)));
+        assert!(aut.remove_edge(2, 1).is_none());
+
+        aut.redirect_edges(3, 1).unwrap();
+        assert_eq!(aut.body().predecessors(1), [0, 1, 3]);
+        assert_eq!(aut.body()[1].edges()[0].state_conjunction().0, [2, 1]);
+        assert!(aut.verify().is_ok());
+        assert!(aut.spans().is_none());
+    }
+
+    #[test]
+    fn states_and_headers() {
+        let mut aut = from_hoa(INPUT).unwrap();
+        let removed = aut.remove_state(1).unwrap();
+        assert_eq!(removed.id(), 1);
+        assert_eq!(aut.num_states(), Some(3));
+        assert_eq!(aut.start().len(), 1);
+        assert_eq!(
+            aut.body()
+                .edges()
+                .map(|(source, edge)| (source, edge.state_conjunction().0.clone()))
+                .collect::<Vec<_>>(),
+            vec![(0, vec![1]), (1, vec![2]), (2, vec![0])]
+        );
+        assert!(aut.verify().is_ok());
+
+        aut.set_start([2, 1]).unwrap();
+        assert!(aut.set_start([3]).is_err());
+        assert_eq!(
+            aut.start().iter().map(|c| c.0.clone()).collect::<Vec<_>>(),
+            vec![vec![2], vec![1]]
+        );
+
+        aut.set_acceptance(AcceptanceCondition::parity(3));
+        assert_eq!(aut.acceptance().0, 3);
+        assert!(aut.acceptance_name().is_none());
+        aut.set_acceptance(AcceptanceCondition::Boolean(crate::HoaBool(true)));
+        assert_eq!(aut.acceptance().0, 0);
+        let printed = crate::output::to_hoa(&aut);
+        assert_eq!(HoaAutomaton::try_from(printed.as_str()), Ok(aut));
+    }
+}
@@ -0,0 +1,262 @@
+use std::{fmt::Display, hash::Hash};
+
+use chumsky::{
+    error::{Simple, SimpleReason},
+    Error,
+};
+use itertools::Itertools;
+
+use crate::Span;
+
+/// A structured error that was encountered while tokenizing or parsing a HOA file. It can
+/// be turned into a human readable report with [`ParseError::render`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The range of character offsets in the input that the error refers to.
+    pub span: Span,
+    /// The line on which the span starts, starting at 1.
+    pub line: usize,
+    /// The column in characters at which the span starts, starting at 1.
+    pub column: usize,
+    /// The sorted descriptions of all tokens that would have been accepted instead, where
+    /// `end of input` stands for the end of the input.
+    pub expected: Vec<String>,
+    /// The token that was found, `None` if the input ended unexpectedly.
+    pub found: Option<String>,
+    /// Describes the error.
+    pub message: String,
+    /// What kind of problem the error describes.
+    pub kind: ParseErrorKind,
+}
+
+/// Classifies a [`ParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseErrorKind {
+    /// The input does not follow the grammar of the format.
+    Syntax,
+    /// A deviation from the format that lenient parsing accepts, see
+    /// [`crate::input::ParseOptions`].
+    Quirk,
+    /// An integer does not fit into an [`crate::Id`].
+    IntegerOverflow,
+    /// A state or atomic proposition is referenced that the header does not declare.
+    IdOutOfRange,
+    /// Expressions are nested deeper than the parser supports.
+    NestingTooDeep,
+}
+
+impl ParseErrorKind {
+    /// The chumsky label that marks errors of this kind, syntax errors are unlabelled.
+    fn label(self) -> Option<&'static str> {
+        match self {
+            ParseErrorKind::Syntax => None,
+            ParseErrorKind::Quirk => Some("quirk"),
+            ParseErrorKind::IntegerOverflow => Some("integer overflow"),
+            ParseErrorKind::IdOutOfRange => Some("id out of range"),
+            ParseErrorKind::NestingTooDeep => Some("nesting too deep"),
+        }
+    }
+
+    /// Recovers the kind of a chumsky error from its label.
+    fn of<T: Hash + Eq>(error: &Simple<T>) -> Self {
+        [
+            ParseErrorKind::Quirk,
+            ParseErrorKind::IntegerOverflow,
+            ParseErrorKind::IdOutOfRange,
+            ParseErrorKind::NestingTooDeep,
+        ]
+        .into_iter()
+        .find(|kind| kind.label() == error.label())
+        .unwrap_or(ParseErrorKind::Syntax)
+    }
+}
+
+impl ParseError {
+    /// Converts a chumsky error into a structured error, `input` is used to compute the line
+    /// and column.
+    pub(crate) fn from_simple<T: Display + Hash + Eq>(input: &str, error: Simple<T>) -> Self {
+        let kind = ParseErrorKind::of(&error);
+        let error = error.map(|token| token.to_string());
+        let expected = error
+            .expected()
+            .map(|expected| match expected {
+                Some(expected) => expected.clone(),
+                None => "end of input".to_string(),
+            })
+            .sorted()
+            .collect_vec();
+        let message = match error.reason() {
+            SimpleReason::Unexpected => format!(
+                "{}, expected {}",
+                if error.found().is_some() {
+                    "Unexpected token in input"
+                } else {
+                    "Unexpected end of input"
+                },
+                if expected.is_empty() {
+                    "something else".to_string()
+                } else {
+                    expected.join(", ")
+                }
+            ),
+            SimpleReason::Unclosed { delimiter, .. } => format!("Unclosed delimiter {delimiter}"),
+            SimpleReason::Custom(message) => message.clone(),
+        };
+        let (line, column) = line_column(input, error.span().start);
+        Self {
+            span: error.span(),
+            line,
+            column,
+            expected,
+            found: error.found().cloned(),
+            message,
+            kind,
+        }
+    }
+
+    /// Renders the error as an [`ariadne`] report that shows the offending part of `input`,
+    /// with ANSI colours if `colored` is set.
+    #[cfg(feature = "ariadne")]
+    pub fn render(&self, input: &str, colored: bool) -> String {
+        let label = match &self.found {
+            Some(found) => format!("Unexpected token {found}"),
+            None if self.expected.is_empty() => self.message.clone(),
+            None => "Unexpected end of input".to_string(),
+        };
+        render_report(input, self.span.clone(), &self.message, &label, colored)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Converts a list of chumsky errors into structured errors.
+pub(crate) fn parse_errors<T: Display + Hash + Eq>(
+    input: &str,
+    errors: impl IntoIterator<Item = Simple<T>>,
+) -> Vec<ParseError> {
+    errors
+        .into_iter()
+        .map(|error| ParseError::from_simple(input, error))
+        .collect()
+}
+
+/// Creates a custom error of the given kind.
+pub(crate) fn custom<T: Hash + Eq>(
+    kind: ParseErrorKind,
+    span: Span,
+    message: impl ToString,
+) -> Simple<T> {
+    let error = Simple::custom(span, message);
+    match kind.label() {
+        Some(label) => error.with_label(label),
+        None => error,
+    }
+}
+
+/// Creates an error for a deviation from the format that lenient parsing accepts.
+pub(crate) fn quirk<T: Hash + Eq>(span: Span, message: impl ToString) -> Simple<T> {
+    custom(ParseErrorKind::Quirk, span, message)
+}
+
+/// Returns true if the error was created by [`quirk`].
+pub(crate) fn is_quirk<T: Hash + Eq>(error: &Simple<T>) -> bool {
+    ParseErrorKind::of(error) == ParseErrorKind::Quirk
+}
+
+/// Returns the line and column, both starting at 1, of the given character offset.
+fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    input
+        .chars()
+        .take(offset)
+        .fold((1, 1), |(line, column), c| match c {
+            '\n' => (line + 1, 1),
+            _ => (line, column + 1),
+        })
+}
+
+/// Renders a report with the given message and a single label at `span`.
+#[cfg(feature = "ariadne")]
+pub(crate) fn render_report(
+    input: &str,
+    span: Span,
+    message: &str,
+    label: &str,
+    colored: bool,
+) -> String {
+    use ariadne::{Color, Config, Fmt, ReportKind, Source};
+
+    let label = match colored {
+        true => label.fg(Color::Red).to_string(),
+        false => label.to_string(),
+    };
+    let mut output = Vec::new();
+    ariadne::Report::build(ReportKind::Error, (), span.start)
+        .with_config(Config::default().with_color(colored))
+        .with_message(message)
+        .with_label(
+            ariadne::Label::new(span)
+                .with_message(label)
+                .with_color(Color::Red),
+        )
+        .finish()
+        .write(Source::from(input), &mut output)
+        .expect("writing to a vector cannot fail");
+    String::from_utf8_lossy(&output).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FromHoaError, HoaAutomaton};
+
+    const INPUT: &str = "HOA: v1\nStates: 1\nStart: 0\nAP: 0\nAcceptance: 0 t\n--BODY--\nState: 0\n[t & 0 0\n--END--";
+
+    fn parser_errors(input: &str) -> Vec<super::ParseError> {
+        match HoaAutomaton::try_from(input) {
+            Err(FromHoaError::ParserError(errors)) => errors,
+            other => panic!("expected a parser error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn structured_parser_error() {
+        let errors = parser_errors(INPUT);
+        assert_eq!(errors.len(), 1);
+        let error = &errors[0];
+        assert_eq!(error.span, 74..75);
+        assert_eq!((error.line, error.column), (8, 8));
+        assert_eq!(error.found.as_deref(), Some("0"));
+        assert_eq!(error.expected, vec!["&", "]", "|"]);
+        assert_eq!(
+            error.to_string(),
+            "8:8: Unexpected token in input, expected &, ], |"
+        );
+    }
+
+    #[test]
+    fn unexpected_end_of_input() {
+        let errors = parser_errors("HOA: v1\nname: \"unterminated");
+        // the broken `name:` item is skipped, after which the body is missing
+        assert_eq!(errors.len(), 2);
+        for error in &errors {
+            assert_eq!(error.line, 2);
+            assert_eq!(error.found, None);
+            assert!(error.message.starts_with("Unexpected end of input"));
+        }
+        assert_eq!(errors[1].expected, vec!["--BODY--"]);
+    }
+
+    #[cfg(feature = "ariadne")]
+    #[test]
+    fn render_without_colours() {
+        let errors = parser_errors(INPUT);
+        let plain = errors[0].render(INPUT, false);
+        assert!(!plain.contains('\u{1b}'));
+        assert!(plain.contains("8 │ [t & 0 0"));
+        assert!(plain.contains("Unexpected token 0"));
+        assert!(errors[0].render(INPUT, true).contains('\u{1b}'));
+    }
+}
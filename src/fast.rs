@@ -0,0 +1,607 @@
+//! A hand-written lexer and recursive descent parser for well-formed input. Tokens borrow
+//! from the input and integers are parsed while lexing, so the only allocations are the
+//! ones needed for the resulting automaton. The parser does not report errors, it gives
+//! up on the first token that it does not expect and leaves the diagnostics to the chumsky
+//! pipeline in [`crate::input`].
+
+use crate::{
+    input::{MAX_ALIAS_SIZE, MAX_NESTING},
+    AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, AcceptanceInfo, AcceptanceName,
+    AcceptanceSignature, AliasName, Body, Edge, Header, HeaderItem, HoaAutomaton, HoaBool, Id,
+    Label, Property, State, StateConjunction,
+};
+
+/// Signals that the input is not accepted by the fast path.
+#[derive(Debug)]
+pub(crate) struct GiveUp;
+
+type Result<T> = std::result::Result<T, GiveUp>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tok<'a> {
+    Int(Id),
+    /// The contents of a quoted string, escape sequences are not yet resolved.
+    Text(&'a str),
+    Identifier(&'a str),
+    Alias(&'a str),
+    Header(&'a str),
+    Op(u8),
+    Paren(u8),
+    BodyStart,
+    BodyEnd,
+    Fin,
+    Inf,
+}
+
+/// Splits the input into tokens on demand, mirroring [`crate::lexer::tokenizer`].
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    peeked: Option<Option<Tok<'a>>>,
+    /// The number of currently open parentheses in an expression.
+    depth: usize,
+    /// The largest depth that was reached since it was last reset.
+    deepest: usize,
+}
+
+/// An alias that labels can refer to, with the depth of parentheses in its definition.
+struct Alias<'a> {
+    name: &'a str,
+    expression: AbstractLabelExpression,
+    depth: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            peeked: None,
+            depth: 0,
+            deepest: 0,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<Tok<'a>>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+        Ok(self.peeked.expect("a token was just peeked"))
+    }
+
+    fn next(&mut self) -> Result<Option<Tok<'a>>> {
+        let token = self.peek()?;
+        self.peeked = None;
+        Ok(token)
+    }
+
+    /// Consumes the next token if it is equal to `token`.
+    fn eat(&mut self, token: Tok<'a>) -> Result<bool> {
+        let found = self.peek()? == Some(token);
+        if found {
+            self.peeked = None;
+        }
+        Ok(found)
+    }
+
+    fn expect(&mut self, token: Tok<'a>) -> Result<()> {
+        match self.eat(token)? {
+            true => Ok(()),
+            false => Err(GiveUp),
+        }
+    }
+
+    /// Parses an expression after an opening parenthesis and the closing one, gives up if
+    /// this is nested deeper than [`MAX_NESTING`].
+    fn nested<T>(&mut self, expression: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth == MAX_NESTING {
+            return Err(GiveUp);
+        }
+        self.depth += 1;
+        self.deepest = self.deepest.max(self.depth);
+        let inner = expression(self)?;
+        self.expect(Tok::Paren(b')'))?;
+        self.depth -= 1;
+        Ok(inner)
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<()> {
+        loop {
+            let rest = &self.input[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with("/*") {
+                return Ok(());
+            }
+            let end = trimmed[2..].find("*/").ok_or(GiveUp)?;
+            self.pos += end + 4;
+        }
+    }
+
+    fn lex(&mut self) -> Result<Option<Tok<'a>>> {
+        self.skip_trivia()?;
+        let rest = &self.input[self.pos..];
+        let bytes = rest.as_bytes();
+        let Some(&first) = bytes.first() else {
+            return Ok(None);
+        };
+        let (token, length) = match first {
+            b'0'..=b'9' => {
+                let length = bytes.iter().take_while(|c| c.is_ascii_digit()).count();
+                // a leading zero ends the integer, leave such inputs to the chumsky lexer
+                if first == b'0' && length > 1 {
+                    return Err(GiveUp);
+                }
+                let value = rest[..length].parse().map_err(|_| GiveUp)?;
+                (Tok::Int(value), length)
+            }
+            b'-' => [
+                ("--BODY--", Tok::BodyStart),
+                ("--END--", Tok::BodyEnd),
+                ("--ABORT--", Tok::BodyEnd),
+            ]
+            .into_iter()
+            .find(|(keyword, _)| rest.starts_with(keyword))
+            .map(|(keyword, token)| (token, keyword.len()))
+            .ok_or(GiveUp)?,
+            b'"' => {
+                let mut escaped = false;
+                let length = bytes[1..]
+                    .iter()
+                    .position(|c| {
+                        let end = !escaped && *c == b'"';
+                        escaped = !escaped && *c == b'\\';
+                        end
+                    })
+                    .ok_or(GiveUp)?;
+                (Tok::Text(&rest[1..1 + length]), length + 2)
+            }
+            b'!' | b'|' | b'&' => (Tok::Op(first), 1),
+            b'(' | b')' | b'{' | b'}' | b'[' | b']' => (Tok::Paren(first), 1),
+            b'@' => {
+                let length = identifier_length(&bytes[1..]);
+                if length == 0 {
+                    return Err(GiveUp);
+                }
+                (Tok::Alias(&rest[1..1 + length]), length + 1)
+            }
+            _ => {
+                let length = identifier_length(bytes);
+                if length == 0 {
+                    return Err(GiveUp);
+                }
+                let identifier = &rest[..length];
+                match (bytes.get(length), identifier) {
+                    (Some(b':'), _) => (Tok::Header(identifier), length + 1),
+                    (_, "Fin") => (Tok::Fin, length),
+                    (_, "Inf") => (Tok::Inf, length),
+                    _ => (Tok::Identifier(identifier), length),
+                }
+            }
+        };
+        self.pos += length;
+        Ok(Some(token))
+    }
+
+    fn integer(&mut self) -> Result<Id> {
+        match self.next()? {
+            Some(Tok::Int(value)) => Ok(value),
+            _ => Err(GiveUp),
+        }
+    }
+
+    fn text(&mut self) -> Result<String> {
+        match self.next()? {
+            Some(Tok::Text(text)) => Ok(unescape(text)),
+            _ => Err(GiveUp),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str> {
+        match self.next()? {
+            Some(Tok::Identifier(identifier)) => Ok(identifier),
+            _ => Err(GiveUp),
+        }
+    }
+
+    /// Consumes the next token if it is an integer.
+    fn eat_integer(&mut self) -> Result<Option<Id>> {
+        match self.peek()? {
+            Some(Tok::Int(value)) => {
+                self.peeked = None;
+                Ok(Some(value))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Returns the length of the identifier at the start of `bytes`, which is zero if there is
+/// none.
+fn identifier_length(bytes: &[u8]) -> usize {
+    match bytes.first() {
+        Some(c) if c.is_ascii_alphabetic() || *c == b'_' => {
+            1 + bytes[1..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.'))
+                .count()
+        }
+        _ => 0,
+    }
+}
+
+/// Resolves the escape sequences of a quoted string like the chumsky lexer does.
+fn unescape(text: &str) -> String {
+    if !text.contains('\\') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(escaped @ ('"' | '\\')) => out.push(escaped),
+            Some(escaped) => {
+                out.push('\\');
+                out.push(escaped);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses a single automaton, gives up on anything that the strict chumsky pipeline would
+/// not accept in exactly the same way.
+pub(crate) fn parse(input: &str) -> Result<HoaAutomaton> {
+    let mut lexer = Lexer::new(input);
+    let (header, aliases) = header(&mut lexer)?;
+    let body = body(&mut lexer, &aliases)?;
+    if lexer.next()?.is_some() {
+        return Err(GiveUp);
+    }
+    Ok(HoaAutomaton::from_parts(header, body))
+}
+
+/// Parses the header and returns it together with the aliases that it defines.
+fn header<'a>(lexer: &mut Lexer<'a>) -> Result<(Header, Vec<Alias<'a>>)> {
+    lexer.expect(Tok::Header("HOA"))?;
+    let mut items = vec![HeaderItem::Version(lexer.identifier()?.to_string())];
+    let mut aliases = vec![];
+    loop {
+        if lexer.peek()? == Some(Tok::BodyStart) {
+            return Ok((Header::from_vec(items), aliases));
+        }
+        let Some(Tok::Header(name)) = lexer.next()? else {
+            return Err(GiveUp);
+        };
+        let item = match name {
+            "States" => HeaderItem::States(lexer.integer()?),
+            "Start" => {
+                let mut states = vec![];
+                if let Some(state) = lexer.eat_integer()? {
+                    states.push(state);
+                    while lexer.eat(Tok::Op(b'&'))? {
+                        states.push(lexer.integer()?);
+                    }
+                }
+                HeaderItem::Start(StateConjunction(states))
+            }
+            "AP" => {
+                let count = lexer.integer()?;
+                let mut aps = vec![];
+                while let Some(Tok::Text(_)) = lexer.peek()? {
+                    aps.push(lexer.text()?);
+                }
+                if aps.len() != count as usize {
+                    return Err(GiveUp);
+                }
+                HeaderItem::AP(aps)
+            }
+            "Alias" => {
+                let Some(Tok::Alias(name)) = lexer.next()? else {
+                    return Err(GiveUp);
+                };
+                // definitions that refer to other aliases or that may expand to more than
+                // MAX_ALIAS_SIZE tokens are left to the chumsky pipeline
+                let start = lexer.pos;
+                lexer.deepest = 0;
+                let expression = label_expression(lexer, &[])?;
+                if lexer.pos - start > MAX_ALIAS_SIZE {
+                    return Err(GiveUp);
+                }
+                aliases.push(Alias {
+                    name,
+                    expression: expression.clone(),
+                    depth: lexer.deepest,
+                });
+                HeaderItem::Alias(AliasName(name.to_string()), expression)
+            }
+            "Acceptance" => HeaderItem::Acceptance(lexer.integer()?, acceptance(lexer)?),
+            "acc-name" => {
+                let name = AcceptanceName::try_from(lexer.identifier()?.to_string())
+                    .map_err(|_| GiveUp)?;
+                let mut info = vec![];
+                loop {
+                    match lexer.peek()? {
+                        Some(Tok::Identifier(identifier)) => {
+                            info.push(AcceptanceInfo::Identifier(identifier.to_string()))
+                        }
+                        Some(Tok::Int(value)) => info.push(AcceptanceInfo::Int(value)),
+                        _ => break,
+                    }
+                    lexer.next()?;
+                }
+                HeaderItem::AcceptanceName(name, info)
+            }
+            "tool" => {
+                let tool = lexer.text()?;
+                let version = match lexer.peek()? {
+                    Some(Tok::Text(_)) => Some(lexer.text()?),
+                    _ => None,
+                };
+                HeaderItem::Tool(tool, version)
+            }
+            "name" => HeaderItem::Name(lexer.text()?),
+            "properties" => {
+                let mut properties = vec![];
+                loop {
+                    let negated = lexer.eat(Tok::Op(b'!'))?;
+                    match (negated, lexer.peek()?) {
+                        (_, Some(Tok::Identifier(name))) if name != "terminatl" => {
+                            lexer.next()?;
+                            let property =
+                                Property::try_from(name.to_string()).map_err(|_| GiveUp)?;
+                            properties.push(match negated {
+                                true => Property::Negated(Box::new(property)),
+                                false => property,
+                            });
+                        }
+                        (false, _) => break,
+                        _ => return Err(GiveUp),
+                    }
+                }
+                HeaderItem::Properties(properties)
+            }
+            "controllable-AP" => {
+                let mut aps = vec![];
+                while let Some(ap) = lexer.eat_integer()? {
+                    aps.push(ap);
+                }
+                HeaderItem::ControllableAP(aps)
+            }
+            _ => return Err(GiveUp),
+        };
+        items.push(item);
+    }
+}
+
+fn body(lexer: &mut Lexer<'_>, aliases: &[Alias<'_>]) -> Result<Body> {
+    lexer.expect(Tok::BodyStart)?;
+    let mut states = vec![];
+    while !lexer.eat(Tok::BodyEnd)? {
+        lexer.expect(Tok::Header("State"))?;
+        if lexer.peek()? == Some(Tok::Paren(b'[')) {
+            // state labels are not supported
+            return Err(GiveUp);
+        }
+        let id = lexer.integer()?;
+        let comment = match lexer.peek()? {
+            Some(Tok::Text(_)) => Some(lexer.text()?),
+            _ => None,
+        };
+        let state_acceptance = acceptance_signature(lexer)?;
+        let mut edges = vec![];
+        while lexer.eat(Tok::Paren(b'['))? {
+            let label = label_expression(lexer, aliases)?;
+            lexer.expect(Tok::Paren(b']'))?;
+            let mut targets = vec![lexer.integer()?];
+            while lexer.eat(Tok::Op(b'&'))? {
+                targets.push(lexer.integer()?);
+            }
+            let marks = match (&state_acceptance, acceptance_signature(lexer)?) {
+                (None, None) => vec![],
+                (Some(state), None) => state.clone(),
+                (None, Some(edge)) => edge,
+                (Some(state), Some(edge)) => state.iter().copied().chain(edge).collect(),
+            };
+            edges.push(Edge::from_parts(
+                Label(label),
+                StateConjunction(targets),
+                AcceptanceSignature(marks),
+            ));
+        }
+        states.push(State::from_parts(id, comment, edges));
+    }
+    Ok(Body::from(states))
+}
+
+fn acceptance_signature(lexer: &mut Lexer<'_>) -> Result<Option<Vec<Id>>> {
+    if !lexer.eat(Tok::Paren(b'{'))? {
+        return Ok(None);
+    }
+    let mut marks = vec![];
+    while let Some(mark) = lexer.eat_integer()? {
+        marks.push(mark);
+    }
+    lexer.expect(Tok::Paren(b'}'))?;
+    Ok(Some(marks))
+}
+
+fn label_expression(
+    lexer: &mut Lexer<'_>,
+    aliases: &[Alias<'_>],
+) -> Result<AbstractLabelExpression> {
+    let mut disjuncts = vec![label_conjunction(lexer, aliases)?];
+    while lexer.eat(Tok::Op(b'|'))? {
+        disjuncts.push(label_conjunction(lexer, aliases)?);
+    }
+    Ok(match disjuncts.len() {
+        1 => disjuncts.pop().expect("there is one disjunct"),
+        _ => AbstractLabelExpression::Disjunction(disjuncts),
+    })
+}
+
+fn label_conjunction(
+    lexer: &mut Lexer<'_>,
+    aliases: &[Alias<'_>],
+) -> Result<AbstractLabelExpression> {
+    let mut conjuncts = vec![label_unary(lexer, aliases)?];
+    while lexer.eat(Tok::Op(b'&'))? {
+        conjuncts.push(label_unary(lexer, aliases)?);
+    }
+    Ok(match conjuncts.len() {
+        1 => conjuncts.pop().expect("there is one conjunct"),
+        _ => AbstractLabelExpression::Conjunction(conjuncts),
+    })
+}
+
+fn label_unary(lexer: &mut Lexer<'_>, aliases: &[Alias<'_>]) -> Result<AbstractLabelExpression> {
+    let negated = lexer.eat(Tok::Op(b'!'))?;
+    let atom = match lexer.next()? {
+        Some(Tok::Identifier("t")) => AbstractLabelExpression::Boolean(true),
+        Some(Tok::Identifier("f")) => AbstractLabelExpression::Boolean(false),
+        Some(Tok::Int(ap)) => AbstractLabelExpression::Integer(ap),
+        Some(Tok::Paren(b'(')) => lexer.nested(|lexer| label_expression(lexer, aliases))?,
+        Some(Tok::Alias(name)) => {
+            // the chumsky pipeline expands an alias in parentheses, which has to fit into
+            // MAX_NESTING as well
+            let alias = aliases.iter().rev().find(|alias| alias.name == name);
+            match alias {
+                Some(alias) if lexer.depth + 1 + alias.depth <= MAX_NESTING => {
+                    alias.expression.clone()
+                }
+                _ => return Err(GiveUp),
+            }
+        }
+        _ => return Err(GiveUp),
+    };
+    Ok(match negated {
+        true => AbstractLabelExpression::Negated(Box::new(atom)),
+        false => atom,
+    })
+}
+
+fn acceptance(lexer: &mut Lexer<'_>) -> Result<AcceptanceCondition> {
+    let mut condition = acceptance_conjunction(lexer)?;
+    while lexer.eat(Tok::Op(b'|'))? {
+        let right = acceptance_conjunction(lexer)?;
+        condition = AcceptanceCondition::Or(Box::new(condition), Box::new(right));
+    }
+    Ok(condition)
+}
+
+fn acceptance_conjunction(lexer: &mut Lexer<'_>) -> Result<AcceptanceCondition> {
+    let mut condition = acceptance_atom(lexer)?;
+    while lexer.eat(Tok::Op(b'&'))? {
+        let right = acceptance_atom(lexer)?;
+        condition = AcceptanceCondition::And(Box::new(condition), Box::new(right));
+    }
+    Ok(condition)
+}
+
+fn acceptance_atom(lexer: &mut Lexer<'_>) -> Result<AcceptanceCondition> {
+    match lexer.next()? {
+        Some(Tok::Identifier("t")) => Ok(AcceptanceCondition::Boolean(HoaBool(true))),
+        Some(Tok::Identifier("f")) => Ok(AcceptanceCondition::Boolean(HoaBool(false))),
+        Some(token @ (Tok::Fin | Tok::Inf)) => {
+            lexer.expect(Tok::Paren(b'('))?;
+            let atom = match lexer.eat(Tok::Op(b'!'))? {
+                true => AcceptanceAtom::Negative(lexer.integer()?),
+                false => AcceptanceAtom::Positive(lexer.integer()?),
+            };
+            lexer.expect(Tok::Paren(b')'))?;
+            Ok(match token {
+                Tok::Fin => AcceptanceCondition::Fin(atom),
+                _ => AcceptanceCondition::Inf(atom),
+            })
+        }
+        Some(Tok::Paren(b'(')) => lexer.nested(acceptance),
+        _ => Err(GiveUp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        input::{from_hoa, from_hoa_fast},
+        output::{AcceptancePlacement, HoaWriter},
+        random::{random_automaton, RandomAcceptance, RandomOptions},
+    };
+
+    fn assert_agrees(input: &str) {
+        assert_eq!(from_hoa_fast(input), from_hoa(input), "{input}");
+    }
+
+    #[test]
+    fn agrees_with_the_chumsky_pipeline() {
+        for file in [
+            include_str!("../hoa/simpledpa.hoa"),
+            include_str!("../hoa/manysipmledpa.hoa"),
+        ] {
+            for input in file.split_inclusive("--END--") {
+                if input.contains("--BODY--") {
+                    assert!(super::parse(input).is_ok());
+                    assert_agrees(input);
+                }
+            }
+        }
+
+        for seed in 0..32 {
+            let options = RandomOptions {
+                states: 1 + seed as usize % 9,
+                aps: seed as usize % 4,
+                acceptance: RandomAcceptance::Rabin(2),
+                alternating: seed % 4 == 0,
+                ..Default::default()
+            };
+            let aut = random_automaton(&options, seed).unwrap();
+            let mut writer = HoaWriter::new(vec![])
+                .compact(seed % 2 == 0)
+                .acceptance(AcceptancePlacement::State);
+            writer.write(&aut).unwrap();
+            let input = String::from_utf8(writer.into_inner()).unwrap();
+            assert!(super::parse(&input).is_ok(), "{input}");
+            assert_agrees(&input);
+        }
+    }
+
+    #[test]
+    fn falls_back_on_errors_and_quirks() {
+        let valid = r#"HOA: v1.1 /* a comment */ States: 1 Start: 0
+            AP: 2 "a \"quoted\"" "b\\c" tool: "t" name: "n"
+            controllable-AP: 1 properties: !complete trans-labels
+            Acceptance: 1 Inf(!0) | (t & Fin(0))
+            --BODY-- State: 0 "s" {0} [!(0 | 1) & t] 0 & 0 {0} [f] 0 --END--"#;
+        assert!(super::parse(valid).is_ok());
+        assert_agrees(valid);
+
+        let aliased = r#"HOA: v1 States: 1 Start: 0 AP: 2 "a" "b" Alias: @a 0 | !1
+            Alias: @b t Acceptance: 0 t --BODY-- State: 0 [@a & !@a] 0 [@b | 1] 0 --END--"#;
+        assert!(super::parse(aliased).is_ok());
+        assert_agrees(aliased);
+
+        for invalid in [
+            "HOA: v1\nStates: 01\nAcceptance: 0 t\n--BODY--\n--END--",
+            "HOA: v1\nStates: 99999999999\nAcceptance: 0 t\n--BODY--\n--END--",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\nState: 0\n[t] 1\n--END--",
+            "HOA: v2\nStates: 0\nAcceptance: 0 t\n--BODY--\n--END--",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\nproperties: terminatl\n--BODY--\n--END--",
+            "HOA: v1\nStates: 1\nAP: 2 \"a\"\nAcceptance: 0 t\n--BODY--\n--END--",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\nState: 0\n[t & 0 0\n--END--",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\nState: 0\n[t] 0\n",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\n--END-- trailing",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\n--END-- $",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\n--ABORT--",
+            "HOA: v1\nStates: 1\nAcceptance: 0 t\n--BODY--\nState: 0\n[@a] 0\n--END--",
+            "HOA: v1\nAlias: @a t\nAlias: @b !@a\nStates: 1\nAcceptance: 0 t\n--BODY--\n\
+             State: 0\n[@b] 0\n--END--",
+        ] {
+            assert_agrees(invalid);
+        }
+    }
+}
@@ -9,6 +9,7 @@ use crate::Id;
 /// Represents a conjunction over states of a HOA automaton, this
 /// is mostly used as the initial state of the automaton.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateConjunction(pub(crate) Vec<crate::Id>);
 
 impl StateConjunction {
@@ -34,6 +35,7 @@ pub type AtomicProposition = String;
 
 /// Aliases are also named by a string.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AliasName(pub(crate) String);
 
 /// An acceptance atom can be used to build an acceptance condition,
@@ -41,6 +43,7 @@ pub struct AliasName(pub(crate) String);
 /// identifier.
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AcceptanceAtom {
     Positive(Id),
     Negative(Id),
@@ -49,13 +52,14 @@ pub enum AcceptanceAtom {
 /// An acceptance signature is a vector of acceptance set
 /// identifiers, it is associated with an edge.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AcceptanceSignature(pub(crate) Vec<crate::Id>);
 
 impl AcceptanceSignature {
     /// Tries to get the singleton element of the acceptance signature, if it exists.
     /// Returns `None` if the acceptance signature is not a singleton.
     pub fn get_singleton(&self) -> Option<Option<Id>> {
-        if self.len() == 0 {
+        if self.is_empty() {
             Some(None)
         } else if self.len() == 1 {
             Some(Some(self[0]))
@@ -85,11 +89,13 @@ impl Deref for AcceptanceSignature {
 
 /// Represents a boolean value in the HOA format.
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HoaBool(pub bool);
 
 /// An acceptance condition is a positive boolean expression over
 /// [`AcceptanceAtom`]s.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AcceptanceCondition {
     /// Represents that the given atom should appear finitely often.
     Fin(AcceptanceAtom),
@@ -134,6 +140,39 @@ impl AcceptanceCondition {
         AcceptanceCondition::Inf(AcceptanceAtom::Positive(0))
     }
 
+    /// Creates the conjunction of the given conditions, which is `t` if there are none. It
+    /// is built as a balanced tree, so it only nests logarithmically deep.
+    pub fn all(conditions: Vec<AcceptanceCondition>) -> Self {
+        Self::balanced(conditions, AcceptanceCondition::And)
+            .unwrap_or(AcceptanceCondition::Boolean(HoaBool(true)))
+    }
+
+    /// Creates the disjunction of the given conditions, which is `f` if there are none. It
+    /// is built as a balanced tree like [`AcceptanceCondition::all`].
+    pub fn any(conditions: Vec<AcceptanceCondition>) -> Self {
+        Self::balanced(conditions, AcceptanceCondition::Or)
+            .unwrap_or(AcceptanceCondition::Boolean(HoaBool(false)))
+    }
+
+    /// Joins neighbouring conditions until a single one is left.
+    fn balanced(
+        mut conditions: Vec<AcceptanceCondition>,
+        join: fn(Box<AcceptanceCondition>, Box<AcceptanceCondition>) -> AcceptanceCondition,
+    ) -> Option<Self> {
+        while conditions.len() > 1 {
+            let mut operands = conditions.into_iter();
+            conditions = std::iter::from_fn(|| {
+                let left = operands.next()?;
+                Some(match operands.next() {
+                    Some(right) => join(Box::new(left), Box::new(right)),
+                    None => left,
+                })
+            })
+            .collect();
+        }
+        conditions.pop()
+    }
+
     /// Creates a conjunction of two acceptance conditions.
     pub fn and<C: Borrow<AcceptanceCondition>>(&self, other: C) -> Self {
         AcceptanceCondition::And(Box::new(self.clone()), Box::new(other.borrow().clone()))
@@ -158,6 +197,79 @@ impl AcceptanceCondition {
     pub fn id_inf(id: Id) -> Self {
         Self::Inf(AcceptanceAtom::Positive(id))
     }
+
+    /// Evaluates the condition for a run on which, from some point on, every edge
+    /// carries exactly the acceptance sets in `marks`.
+    pub fn satisfied_by(&self, marks: &[Id]) -> bool {
+        match self {
+            AcceptanceCondition::Fin(AcceptanceAtom::Positive(id))
+            | AcceptanceCondition::Inf(AcceptanceAtom::Negative(id)) => !marks.contains(id),
+            AcceptanceCondition::Fin(AcceptanceAtom::Negative(id))
+            | AcceptanceCondition::Inf(AcceptanceAtom::Positive(id)) => marks.contains(id),
+            AcceptanceCondition::And(left, right) => {
+                left.satisfied_by(marks) && right.satisfied_by(marks)
+            }
+            AcceptanceCondition::Or(left, right) => {
+                left.satisfied_by(marks) || right.satisfied_by(marks)
+            }
+            AcceptanceCondition::Boolean(HoaBool(value)) => *value,
+        }
+    }
+
+    /// Returns the dual condition, which is satisfied by a run if and only if this
+    /// condition is not.
+    pub fn dual(&self) -> Self {
+        match self {
+            AcceptanceCondition::Fin(atom) => AcceptanceCondition::Inf(atom.clone()),
+            AcceptanceCondition::Inf(atom) => AcceptanceCondition::Fin(atom.clone()),
+            AcceptanceCondition::And(left, right) => {
+                AcceptanceCondition::Or(Box::new(left.dual()), Box::new(right.dual()))
+            }
+            AcceptanceCondition::Or(left, right) => {
+                AcceptanceCondition::And(Box::new(left.dual()), Box::new(right.dual()))
+            }
+            AcceptanceCondition::Boolean(HoaBool(value)) => {
+                AcceptanceCondition::Boolean(HoaBool(!value))
+            }
+        }
+    }
+
+    /// Returns the number of acceptance sets the condition refers to, which is the largest
+    /// set that occurs in it plus one.
+    pub fn set_count(&self) -> Id {
+        match self {
+            AcceptanceCondition::Fin(
+                AcceptanceAtom::Positive(set) | AcceptanceAtom::Negative(set),
+            )
+            | AcceptanceCondition::Inf(
+                AcceptanceAtom::Positive(set) | AcceptanceAtom::Negative(set),
+            ) => set.saturating_add(1),
+            AcceptanceCondition::And(left, right) | AcceptanceCondition::Or(left, right) => {
+                left.set_count().max(right.set_count())
+            }
+            AcceptanceCondition::Boolean(_) => 0,
+        }
+    }
+
+    /// Renames every acceptance set `i` occurring in the condition to `i + offset`.
+    pub fn shift(&self, offset: Id) -> Self {
+        let shift_atom = |atom: &AcceptanceAtom| match atom {
+            AcceptanceAtom::Positive(id) => AcceptanceAtom::Positive(id + offset),
+            AcceptanceAtom::Negative(id) => AcceptanceAtom::Negative(id + offset),
+        };
+        match self {
+            AcceptanceCondition::Fin(atom) => AcceptanceCondition::Fin(shift_atom(atom)),
+            AcceptanceCondition::Inf(atom) => AcceptanceCondition::Inf(shift_atom(atom)),
+            AcceptanceCondition::And(left, right) => AcceptanceCondition::And(
+                Box::new(left.shift(offset)),
+                Box::new(right.shift(offset)),
+            ),
+            AcceptanceCondition::Or(left, right) => {
+                AcceptanceCondition::Or(Box::new(left.shift(offset)), Box::new(right.shift(offset)))
+            }
+            AcceptanceCondition::Boolean(value) => AcceptanceCondition::Boolean(value.clone()),
+        }
+    }
 }
 
 /// Represents the name of a type of acceptance condition.
@@ -257,12 +369,28 @@ pub enum Property {
     Terminal,
     Tight,
     Colored,
+    /// Since v1.1, the automaton is deterministic once an accepting cycle is reached.
+    SemiDeterministic,
+    /// Since v1.1, states that the given property does not hold, written with a `!` prefix.
+    Negated(Box<Property>),
+}
+
+impl Property {
+    /// Returns true if the property can only be written in HOA v1.1 or later, which is
+    /// the case for negated properties and `semi-deterministic`.
+    pub fn requires_v1_1(&self) -> bool {
+        matches!(self, Property::Negated(_) | Property::SemiDeterministic)
+    }
 }
 
 impl TryFrom<String> for Property {
     type Error = String;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Some(negated) = value.strip_prefix('!') {
+            return Property::try_from(negated.to_string())
+                .map(|property| Property::Negated(Box::new(property)));
+        }
         match value.as_str() {
             "state-labels" => Ok(Property::StateLabels),
             "trans-labels" => Ok(Property::TransLabels),
@@ -279,9 +407,10 @@ impl TryFrom<String> for Property {
             "weak" => Ok(Property::Weak),
             "very-weak" => Ok(Property::VeryWeak),
             "inherently-weak" => Ok(Property::InherentlyWeak),
-            "terminatl" => Ok(Property::Terminal),
+            "terminal" => Ok(Property::Terminal),
             "tight" => Ok(Property::Tight),
             "colored" => Ok(Property::Colored),
+            "semi-deterministic" => Ok(Property::SemiDeterministic),
             unknown => Err(format!("{} is not a valid property", unknown)),
         }
     }
@@ -292,6 +421,8 @@ impl TryFrom<String> for Property {
 /// can be obtained in the [HOA docs](https://adl.github.io/hoaf/#acc-name).
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum AcceptanceInfo {
     Int(crate::Id),
     Identifier(String),
@@ -322,4 +453,17 @@ mod tests {
                 .or(AcceptanceCondition::id_fin(1).and(AcceptanceCondition::id_inf(2)))
         );
     }
+
+    #[test]
+    fn balanced_conjunctions_and_disjunctions() {
+        let atoms = (0..5).map(AcceptanceCondition::id_inf).collect();
+        assert_eq!(
+            AcceptanceCondition::all(atoms).to_string(),
+            "(((Inf(0) & Inf(1)) & (Inf(2) & Inf(3))) & Inf(4))"
+        );
+        let fin = AcceptanceCondition::id_fin(0);
+        assert_eq!(AcceptanceCondition::any(vec![fin.clone()]), fin);
+        assert_eq!(AcceptanceCondition::any(vec![]).to_string(), "f");
+        assert_eq!(AcceptanceCondition::all(vec![]).to_string(), "t");
+    }
 }
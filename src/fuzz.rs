@@ -0,0 +1,129 @@
+//! Mutation based fuzzing of the parsers. The automata in `hoa/` are mutated with a seeded
+//! random generator, so failures are reproducible, and every mutant is fed to all entry
+//! points, which have to return instead of panicking.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    first_automaton_split_position,
+    input::{from_hoa, from_hoa_fast, from_hoa_partial, from_hoa_with, ParseOptions, MAX_NESTING},
+    output::to_hoa,
+    parse_hoa_automata, HoaAutomaton,
+};
+
+const CORPUS: [&str; 2] = [
+    include_str!("../hoa/simpledpa.hoa"),
+    include_str!("../hoa/manysipmledpa.hoa"),
+];
+
+/// Fragments that are spliced into the input, chosen to provoke integer overflows,
+/// references to undeclared ids, deep nesting and broken strings or comments.
+const FRAGMENTS: [&str; 24] = [
+    "99999999999999999999999",
+    "4294967296",
+    "4294967295",
+    "65536",
+    "((((((((",
+    "))",
+    "!",
+    " & ",
+    " | ",
+    "[",
+    "]",
+    "{",
+    "}",
+    "\"",
+    "/*",
+    "*/",
+    "--BODY--",
+    "--END--",
+    "--ABORT--",
+    "State: ",
+    "States: ",
+    "Acceptance: ",
+    "Fin(",
+    "\u{1F980}",
+];
+
+/// Applies a few random byte level edits to `input`.
+fn mutate(input: &str, rng: &mut StdRng) -> String {
+    let mut bytes = input.as_bytes().to_vec();
+    for _ in 0..rng.gen_range(1..=4) {
+        let position = rng.gen_range(0..=bytes.len());
+        match rng.gen_range(0..5) {
+            0 if position < bytes.len() => bytes[position] ^= 1 << rng.gen_range(0..8),
+            1 => bytes.truncate(position),
+            2 => {
+                let fragment = FRAGMENTS.choose(rng).expect("there are fragments");
+                bytes.splice(position..position, fragment.bytes());
+            }
+            3 => {
+                let end = bytes.len().min(position + rng.gen_range(1..16));
+                bytes.drain(position..end);
+            }
+            _ => {
+                let start = rng.gen_range(0..=bytes.len());
+                let end = bytes.len().min(start + rng.gen_range(1..64));
+                let copy = bytes[start..end].to_vec();
+                bytes.splice(position..position, copy);
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Runs all entry points on the input, an automaton that is accepted has to survive
+/// verification and printing as well.
+fn exercise(input: &str) {
+    let check = |automaton: HoaAutomaton| {
+        let _ = automaton.verify();
+        let _ = HoaAutomaton::try_from(to_hoa(&automaton).as_str());
+    };
+    let strict = from_hoa(input);
+    assert_eq!(from_hoa_fast(input), strict);
+    if let Ok(automaton) = strict {
+        check(automaton);
+    }
+    if let Ok((automaton, _)) = from_hoa_with(input, ParseOptions::lenient()) {
+        check(automaton);
+    }
+    if let (Some(automaton), _) = from_hoa_partial(input) {
+        check(automaton);
+    }
+    first_automaton_split_position(input);
+    parse_hoa_automata(input);
+}
+
+#[test]
+fn mutated_corpus_never_panics() {
+    let mut rng = StdRng::seed_from_u64(0x40a);
+    for file in CORPUS {
+        for _ in 0..100 {
+            let input = mutate(file, &mut rng);
+            let result = catch_unwind(AssertUnwindSafe(|| exercise(&input)));
+            assert!(result.is_ok(), "panicked on input:\n{input}");
+        }
+    }
+}
+
+#[test]
+fn deep_nesting_is_rejected() {
+    for nesting in [MAX_NESTING + 1, 2000] {
+        let open = "(".repeat(nesting);
+        let close = ")".repeat(nesting);
+        let label = format!(
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAcceptance: 0 t\n--BODY--\n\
+             State: 0\n[{open}0{close}] 0\n--END--"
+        );
+        let acceptance = format!(
+            "HOA: v1\nStates: 1\nStart: 0\nAcceptance: 1 {open}Inf(0){close}\n--BODY--\n\
+             State: 0\n[t] 0\n--END--"
+        );
+        for input in [label, acceptance] {
+            exercise(&input);
+            assert!(from_hoa(&input).is_err());
+        }
+    }
+}
@@ -0,0 +1,787 @@
+//! Conversion between automata and the XML file format (GFF) of the
+//! [GOAL](http://goal.im.ntu.edu.tw/) tool. Only automata with labels on the transitions
+//! are supported, their alphabet is either `Propositional`, where a label is a conjunction
+//! of literals like `a ~b`, or `Boolean`, where a label is a formula like `a & ~(b | c)`.
+//! Acceptance is state-based and one of
+//! - `Buchi` and `CoBuchi`, given by a set of states that has to be visited infinitely or
+//!   finitely often,
+//! - `Rabin` and `Streett`, given by pairs of sets `E` and `F`. A Rabin pair is satisfied if
+//!   `E` is visited finitely often and `F` infinitely often, a Streett pair if `F` is
+//!   visited infinitely often or `E` finitely often,
+//! - `Parity`, given by the sets of states with the priorities `0`, `1`, ..., the least
+//!   priority that is visited infinitely often has to be even.
+
+use itertools::Itertools;
+use roxmltree::{Document, Node};
+
+use crate::{
+    input::{max_ap, MAX_NESTING},
+    AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, AcceptanceInfo, AcceptanceName,
+    AcceptanceSignature, Body, Edge, Header, HeaderItem, HoaAutomaton, Id, Label, Property, State,
+    StateConjunction,
+};
+
+/// Reads an automaton in GOAL's XML format. The states are numbered in the order in which
+/// they are listed. For Büchi and co-Büchi acceptance there is a single acceptance set,
+/// otherwise set `2i` is the set `E` and set `2i + 1` the set `F` of the i-th pair, or set
+/// `i` holds the states with priority `i`. The acceptance sets of a state are put on the
+/// edges that leave it.
+pub fn from_goal(input: &str) -> Result<HoaAutomaton, String> {
+    let document = Document::parse(input).map_err(|err| err.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("structure") || root.attribute("type") != Some("FiniteStateAutomaton") {
+        return Err("Expected a structure of type FiniteStateAutomaton".to_string());
+    }
+    if root.attribute("label-on").unwrap_or("Transition") != "Transition" {
+        return Err("Only labels on transitions are supported".to_string());
+    }
+
+    let alphabet = child(root, "alphabet")?;
+    let boolean = match alphabet.attribute("type") {
+        Some("Propositional") => false,
+        Some("Boolean") => true,
+        other => return Err(format!("Unsupported alphabet type {other:?}")),
+    };
+    let aps = children(alphabet, "prop").map(text).collect_vec();
+
+    let sids = children(child(root, "stateSet")?, "state")
+        .map(|state| {
+            state
+                .attribute("sid")
+                .ok_or_else(|| "A state has no sid".to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(sid) = sids.iter().duplicates().next() {
+        return Err(format!("The state {sid} is listed twice"));
+    }
+    let state = |node: Node| {
+        let sid = text(node);
+        sids.iter()
+            .position(|s| *s == sid)
+            .map(|position| position as Id)
+            .ok_or_else(|| format!("There is no state {sid}"))
+    };
+    let states = |node: Node| {
+        children(node, "stateID")
+            .map(state)
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let acc = child(root, "acc")?;
+    let mut marks = vec![vec![]; sids.len()];
+    let mut mark = |states: Vec<Id>, set: Id| {
+        for state in states {
+            marks[state as usize].push(set);
+        }
+    };
+    let (name, condition) = match acc.attribute("type") {
+        Some(kind @ ("Buchi" | "CoBuchi")) => {
+            mark(states(acc)?, 0);
+            match kind {
+                "Buchi" => (
+                    HeaderItem::AcceptanceName(AcceptanceName::Buchi, vec![]),
+                    AcceptanceCondition::id_inf(0),
+                ),
+                _ => (
+                    HeaderItem::AcceptanceName(AcceptanceName::CoBuchi, vec![]),
+                    AcceptanceCondition::id_fin(0),
+                ),
+            }
+        }
+        Some(kind @ ("Rabin" | "Streett")) => {
+            let rabin = kind == "Rabin";
+            let pairs = children(acc, "accPair").collect_vec();
+            for (i, pair) in pairs.iter().enumerate() {
+                mark(states(child(*pair, "E")?)?, 2 * i as Id);
+                mark(states(child(*pair, "F")?)?, 2 * i as Id + 1);
+            }
+            let pair_conditions = (0..pairs.len() as Id).map(|i| {
+                let (fin, inf) = (
+                    AcceptanceCondition::id_fin(2 * i),
+                    AcceptanceCondition::id_inf(2 * i + 1),
+                );
+                match rabin {
+                    true => fin.and(inf),
+                    false => fin.or(inf),
+                }
+            });
+            let condition = match rabin {
+                true => AcceptanceCondition::any(pair_conditions.collect()),
+                false => AcceptanceCondition::all(pair_conditions.collect()),
+            };
+            let name = match rabin {
+                true => AcceptanceName::Rabin,
+                false => AcceptanceName::Streett,
+            };
+            let info = vec![AcceptanceInfo::Int(pairs.len() as Id)];
+            (HeaderItem::AcceptanceName(name, info), condition)
+        }
+        Some("Parity") => {
+            let sets = children(acc, "accSet").collect_vec();
+            let mut priorities = 0;
+            // the parity condition nests one level deeper per priority
+            for set in sets {
+                let priority = set
+                    .attribute("index")
+                    .and_then(|index| index.parse::<Id>().ok())
+                    .filter(|index| (*index as usize) < MAX_NESTING)
+                    .ok_or("A set of the parity condition has an invalid index")?;
+                priorities = priorities.max(priority + 1);
+                mark(states(set)?, priority);
+            }
+            let info = ["min", "even"].map(AcceptanceInfo::identifier).into_iter();
+            let info = info.chain([AcceptanceInfo::Int(priorities)]).collect();
+            (
+                HeaderItem::AcceptanceName(AcceptanceName::Parity, info),
+                AcceptanceCondition::parity(priorities),
+            )
+        }
+        other => return Err(format!("Unsupported acceptance type {other:?}")),
+    };
+
+    let mut edges = vec![vec![]; sids.len()];
+    for transition in children(child(root, "transitionSet")?, "transition") {
+        let from = state(child(transition, "from")?)?;
+        let to = state(child(transition, "to")?)?;
+        let read = text(child(transition, "read")?);
+        let label = match boolean {
+            true => formula(&read, &aps)?,
+            false => literals(&read, &aps)?,
+        };
+        edges[from as usize].push((label, to));
+    }
+
+    let body = edges
+        .into_iter()
+        .zip(marks)
+        .enumerate()
+        .map(|(id, (edges, marks))| {
+            let marks = AcceptanceSignature(marks.into_iter().sorted().dedup().collect());
+            let edges = edges.into_iter().map(|(label, to)| {
+                Edge::from_parts(Label(label), StateConjunction::singleton(to), marks.clone())
+            });
+            State::from_parts(id as Id, None, edges.collect())
+        })
+        .collect_vec();
+
+    let mut header = vec![HeaderItem::v1()];
+    if let Some(name) = root
+        .children()
+        .find(|node| node.has_tag_name("name"))
+        .map(text)
+        .filter(|name| !name.is_empty())
+    {
+        header.push(HeaderItem::Name(name));
+    }
+    header.push(HeaderItem::States(sids.len() as Id));
+    if let Ok(initial) = child(root, "initialStateSet") {
+        header.extend(
+            states(initial)?
+                .into_iter()
+                .map(|state| HeaderItem::Start(StateConjunction::singleton(state))),
+        );
+    }
+    let sets = condition.set_count();
+    header.push(HeaderItem::AP(aps));
+    header.push(name);
+    header.push(HeaderItem::Acceptance(sets, condition));
+    header.push(HeaderItem::Properties(vec![
+        Property::TransLabels,
+        Property::ExplicitLabels,
+        Property::StateAcceptance,
+    ]));
+    let aut = HoaAutomaton::from_parts(Header::from_vec(header), Body::from(body));
+    aut.verify()?;
+    Ok(aut)
+}
+
+/// Writes the automaton in GOAL's XML format. The alphabet is `Propositional` if every
+/// label is a conjunction of literals and `Boolean` otherwise, edges labelled `f` are left
+/// out. Propositions that are used but not declared are named `p` followed by their index.
+///
+/// Fails if the acceptance condition is not a Büchi, co-Büchi, Rabin, Streett or min even
+/// parity condition in the form that [`from_goal`] produces, if the edges that leave a
+/// state differ in their acceptance sets or if an initial state or edge is universal.
+pub fn to_goal(aut: &HoaAutomaton) -> Result<String, String> {
+    let condition = &aut.acceptance().1;
+    let (kind, sets) =
+        acceptance_sets(condition).ok_or("The acceptance condition cannot be expressed in GOAL")?;
+    let mut marks = vec![];
+    for state in aut.body() {
+        let Ok(signature) = state
+            .edges()
+            .iter()
+            .map(|edge| edge.acceptance_signature())
+            .dedup()
+            .at_most_one()
+        else {
+            return Err(format!(
+                "The edges leaving state {} differ in their acceptance sets",
+                state.id()
+            ));
+        };
+        marks.push((state.id(), signature.map_or(&[][..], |s| &s.0[..])));
+    }
+    let in_set = |set: Id| {
+        marks
+            .iter()
+            .filter(move |(_, marks)| marks.contains(&set))
+            .map(|(id, _)| *id)
+    };
+    let start = aut
+        .start()
+        .into_iter()
+        .map(|conjunction| {
+            conjunction
+                .get_singleton()
+                .ok_or("GOAL does not support universal initial states")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if aut
+        .body()
+        .edges()
+        .any(|(_, edge)| edge.state_conjunction().get_singleton().is_none())
+    {
+        return Err("GOAL does not support universal edges".to_string());
+    }
+
+    let propositional = aut
+        .body()
+        .edges()
+        .all(|(_, edge)| conjunction_of_literals(&edge.label().0).is_some());
+    // propositions that are used but not declared get a fresh name `p{ap}`
+    let used = aut
+        .body()
+        .edges()
+        .filter_map(|(_, edge)| max_ap(&edge.label().0))
+        .max()
+        .map_or(0, |ap| ap as usize + 1);
+    let mut aps = aut.aps().to_vec();
+    for ap in aps.len()..used {
+        let mut fresh = format!("p{ap}");
+        while aps.contains(&fresh) {
+            fresh.push('_');
+        }
+        aps.push(fresh);
+    }
+    let name = |ap: Id| aps[ap as usize].clone();
+
+    let mut lines = vec![
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>".to_string(),
+        "<structure label-on=\"Transition\" type=\"FiniteStateAutomaton\">".to_string(),
+    ];
+    match aut.header().iter().find_map(|item| match item {
+        HeaderItem::Name(name) => Some(name),
+        _ => None,
+    }) {
+        Some(name) => lines.push(format!("  <name>{}</name>", escape(name))),
+        None => lines.push("  <name/>".to_string()),
+    }
+    let alphabet = if propositional {
+        "Propositional"
+    } else {
+        "Boolean"
+    };
+    lines.push(format!("  <alphabet type=\"{alphabet}\">"));
+    for ap in &aps {
+        lines.push(format!("    <prop>{}</prop>", escape(ap)));
+    }
+    lines.push("  </alphabet>".to_string());
+    lines.push("  <stateSet>".to_string());
+    for state in aut.body() {
+        lines.push(format!("    <state sid=\"{}\"/>", state.id()));
+    }
+    lines.push("  </stateSet>".to_string());
+    lines.push("  <transitionSet complete=\"false\">".to_string());
+    let edges = aut
+        .body()
+        .edges()
+        .filter(|(_, edge)| edge.label().0 != AbstractLabelExpression::Boolean(false));
+    for (tid, (from, edge)) in edges.enumerate() {
+        let label = &edge.label().0;
+        let read = match conjunction_of_literals(label).filter(|_| propositional) {
+            Some(literals) if literals.is_empty() => "True".to_string(),
+            Some(literals) => literals
+                .into_iter()
+                .map(|(ap, positive)| format!("{}{}", if positive { "" } else { "~" }, name(ap)))
+                .join(" "),
+            None => write_formula(label, &name),
+        };
+        lines.push(format!("    <transition tid=\"{tid}\">"));
+        lines.push(format!("      <from>{from}</from>"));
+        lines.push(format!("      <to>{}</to>", edge.state_conjunction().0[0]));
+        lines.push(format!("      <read>{}</read>", escape(&read)));
+        lines.push("    </transition>".to_string());
+    }
+    lines.push("  </transitionSet>".to_string());
+    lines.push("  <initialStateSet>".to_string());
+    for state in start {
+        lines.push(format!("    <stateID>{state}</stateID>"));
+    }
+    lines.push("  </initialStateSet>".to_string());
+
+    let state_ids = |indent: &str, set: Id| {
+        in_set(set)
+            .map(|id| format!("{indent}<stateID>{id}</stateID>"))
+            .collect_vec()
+    };
+    lines.push(format!("  <acc type=\"{}\">", kind.name()));
+    match kind {
+        Kind::Buchi | Kind::CoBuchi => lines.extend(state_ids("    ", sets[0])),
+        Kind::Rabin | Kind::Streett => {
+            for (e, f) in sets.into_iter().tuples() {
+                lines.push("    <accPair>".to_string());
+                for (tag, set) in [("E", e), ("F", f)] {
+                    lines.push(format!("      <{tag}>"));
+                    lines.extend(state_ids("        ", set));
+                    lines.push(format!("      </{tag}>"));
+                }
+                lines.push("    </accPair>".to_string());
+            }
+        }
+        Kind::Parity => {
+            for set in sets {
+                lines.push(format!("    <accSet index=\"{set}\">"));
+                lines.extend(state_ids("      ", set));
+                lines.push("    </accSet>".to_string());
+            }
+        }
+    }
+    lines.push("  </acc>".to_string());
+    lines.push("</structure>".to_string());
+    Ok(lines.join("\n") + "\n")
+}
+
+/// The kinds of acceptance conditions that GOAL supports.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Buchi,
+    CoBuchi,
+    Rabin,
+    Streett,
+    Parity,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Buchi => "Buchi",
+            Kind::CoBuchi => "CoBuchi",
+            Kind::Rabin => "Rabin",
+            Kind::Streett => "Streett",
+            Kind::Parity => "Parity",
+        }
+    }
+}
+
+/// Recognizes the kind of an acceptance condition and returns the sets it refers to, the
+/// sets `E` and `F` of the pairs alternate for Rabin and Streett conditions and the sets
+/// are ordered by priority for parity conditions.
+fn acceptance_sets(condition: &AcceptanceCondition) -> Option<(Kind, Vec<Id>)> {
+    use AcceptanceAtom::Positive;
+    use AcceptanceCondition::{Fin, Inf};
+    if let Inf(Positive(set)) = condition {
+        return Some((Kind::Buchi, vec![*set]));
+    }
+    if let Fin(Positive(set)) = condition {
+        return Some((Kind::CoBuchi, vec![*set]));
+    }
+    let priorities = condition.set_count();
+    if priorities > 1 && *condition == AcceptanceCondition::parity(priorities) {
+        return Some((Kind::Parity, (0..priorities).collect()));
+    }
+    pairs(condition, true)
+        .map(|sets| (Kind::Rabin, sets))
+        .or_else(|| pairs(condition, false).map(|sets| (Kind::Streett, sets)))
+}
+
+/// Collects the sets `E` and `F` of a disjunction of pairs `Fin(E) & Inf(F)`, or with
+/// `rabin` false of a conjunction of pairs `Fin(E) | Inf(F)`.
+fn pairs(condition: &AcceptanceCondition, rabin: bool) -> Option<Vec<Id>> {
+    use AcceptanceAtom::Positive;
+    use AcceptanceCondition::{And, Fin, Inf, Or};
+    let mut sets = vec![];
+    let mut pending = vec![condition];
+    while let Some(condition) = pending.pop() {
+        match (condition, rabin) {
+            (Or(left, right), true) | (And(left, right), false) => {
+                pending.extend([&**right, &**left])
+            }
+            (And(left, right), true) | (Or(left, right), false) => match (&**left, &**right) {
+                (Fin(Positive(e)), Inf(Positive(f))) => sets.extend([*e, *f]),
+                _ => return None,
+            },
+            _ => return None,
+        }
+    }
+    Some(sets)
+}
+
+/// Returns the literals of a label that is a conjunction of literals, or `t`.
+fn conjunction_of_literals(label: &AbstractLabelExpression) -> Option<Vec<(Id, bool)>> {
+    let literal = |expression: &AbstractLabelExpression| match expression {
+        AbstractLabelExpression::Integer(ap) => Some((*ap, true)),
+        AbstractLabelExpression::Negated(negated) => match &**negated {
+            AbstractLabelExpression::Integer(ap) => Some((*ap, false)),
+            _ => None,
+        },
+        _ => None,
+    };
+    match label {
+        AbstractLabelExpression::Boolean(true) => Some(vec![]),
+        AbstractLabelExpression::Conjunction(conjuncts) => conjuncts.iter().map(literal).collect(),
+        literal_label => literal(literal_label).map(|literal| vec![literal]),
+    }
+}
+
+/// Writes a label as a formula of a `Boolean` alphabet.
+fn write_formula(label: &AbstractLabelExpression, name: &dyn Fn(Id) -> String) -> String {
+    let nested = |operand: &AbstractLabelExpression| match operand {
+        AbstractLabelExpression::Conjunction(operands)
+        | AbstractLabelExpression::Disjunction(operands)
+            if operands.len() > 1 =>
+        {
+            format!("({})", write_formula(operand, name))
+        }
+        _ => write_formula(operand, name),
+    };
+    match label {
+        AbstractLabelExpression::Boolean(true) => "True".to_string(),
+        AbstractLabelExpression::Boolean(false) => "False".to_string(),
+        AbstractLabelExpression::Integer(ap) => name(*ap),
+        AbstractLabelExpression::Negated(operand) => format!("~{}", nested(operand)),
+        AbstractLabelExpression::Conjunction(operands) if operands.is_empty() => "True".into(),
+        AbstractLabelExpression::Disjunction(operands) if operands.is_empty() => "False".into(),
+        AbstractLabelExpression::Conjunction(operands) => operands.iter().map(nested).join(" & "),
+        AbstractLabelExpression::Disjunction(operands) => operands.iter().map(nested).join(" | "),
+    }
+}
+
+/// Escapes the characters that have a special meaning in XML text and attributes.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns the first child element with the given tag name.
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Result<Node<'a, 'input>, String> {
+    node.children()
+        .find(|child| child.has_tag_name(tag))
+        .ok_or_else(|| {
+            format!(
+                "The element {} has no {tag} element",
+                node.tag_name().name()
+            )
+        })
+}
+
+/// Iterates over the child elements with the given tag name.
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+/// Returns the trimmed text content of an element.
+fn text(node: Node) -> String {
+    node.text().unwrap_or_default().trim().to_string()
+}
+
+/// Looks up the index of the atomic proposition with the given name.
+fn proposition(name: &str, aps: &[String]) -> Result<AbstractLabelExpression, String> {
+    aps.iter()
+        .position(|ap| ap == name)
+        .map(|ap| AbstractLabelExpression::Integer(ap as Id))
+        .ok_or_else(|| format!("The proposition {name:?} is not in the alphabet"))
+}
+
+/// Parses the label of a `Propositional` alphabet, a list of possibly negated propositions.
+fn literals(read: &str, aps: &[String]) -> Result<AbstractLabelExpression, String> {
+    let literals = read
+        .split_whitespace()
+        .filter(|literal| *literal != "True")
+        .map(|literal| match literal.strip_prefix('~') {
+            Some(name) => {
+                proposition(name, aps).map(|ap| AbstractLabelExpression::Negated(Box::new(ap)))
+            }
+            None => proposition(literal, aps),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(match <[_; 1]>::try_from(literals) {
+        Ok([single]) => single,
+        Err(literals) if literals.is_empty() => AbstractLabelExpression::Boolean(true),
+        Err(literals) => AbstractLabelExpression::Conjunction(literals),
+    })
+}
+
+/// Parses the label of a `Boolean` alphabet, a formula over the propositions and the
+/// constants `True` and `False` with the operators `~` (or `!`), `&` and `|`.
+fn formula(read: &str, aps: &[String]) -> Result<AbstractLabelExpression, String> {
+    let mut tokens = vec![];
+    let mut rest = read.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let length = match c {
+            '~' | '!' | '(' | ')' => 1,
+            '&' | '|' if rest[1..].starts_with(c) => 2,
+            '&' | '|' => 1,
+            _ => rest
+                .find(|c: char| c.is_whitespace() || "~!()&|".contains(c))
+                .unwrap_or(rest.len()),
+        };
+        tokens.push(&rest[..length]);
+        rest = rest[length..].trim_start();
+    }
+    let mut parser = Formula {
+        tokens: &tokens,
+        aps,
+        depth: 0,
+    };
+    let expression = parser.disjunction()?;
+    match parser.tokens.first() {
+        None => Ok(expression),
+        Some(token) => Err(format!("Unexpected {token:?} in the label {read:?}")),
+    }
+}
+
+/// A recursive descent parser for the formulas of a `Boolean` alphabet.
+struct Formula<'a> {
+    tokens: &'a [&'a str],
+    aps: &'a [String],
+    depth: usize,
+}
+
+impl<'a> Formula<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let (first, rest) = self.tokens.split_first()?;
+        self.tokens = rest;
+        Some(first)
+    }
+
+    /// Parses operands separated by the given operator.
+    fn operands(
+        &mut self,
+        operator: [&str; 2],
+        operand: fn(&mut Self) -> Result<AbstractLabelExpression, String>,
+    ) -> Result<Vec<AbstractLabelExpression>, String> {
+        let mut operands = vec![operand(self)?];
+        while self.tokens.first().is_some_and(|t| operator.contains(t)) {
+            self.next();
+            operands.push(operand(self)?);
+        }
+        Ok(operands)
+    }
+
+    fn disjunction(&mut self) -> Result<AbstractLabelExpression, String> {
+        let disjuncts = self.operands(["|", "||"], Self::conjunction)?;
+        Ok(match <[_; 1]>::try_from(disjuncts) {
+            Ok([single]) => single,
+            Err(disjuncts) => AbstractLabelExpression::Disjunction(disjuncts),
+        })
+    }
+
+    fn conjunction(&mut self) -> Result<AbstractLabelExpression, String> {
+        let conjuncts = self.operands(["&", "&&"], Self::unary)?;
+        Ok(match <[_; 1]>::try_from(conjuncts) {
+            Ok([single]) => single,
+            Err(conjuncts) => AbstractLabelExpression::Conjunction(conjuncts),
+        })
+    }
+
+    fn unary(&mut self) -> Result<AbstractLabelExpression, String> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(format!(
+                "A label is nested deeper than {MAX_NESTING} levels"
+            ));
+        }
+        let expression = match self.next() {
+            Some("~" | "!") => AbstractLabelExpression::Negated(Box::new(self.unary()?)),
+            Some("(") => {
+                let expression = self.disjunction()?;
+                match self.next() {
+                    Some(")") => expression,
+                    _ => return Err("Expected ) in a label".to_string()),
+                }
+            }
+            Some("True" | "true") => AbstractLabelExpression::Boolean(true),
+            Some("False" | "false") => AbstractLabelExpression::Boolean(false),
+            Some(name) if !"&|)".contains(name) => proposition(name, self.aps)?,
+            Some(token) => return Err(format!("Unexpected {token:?} in a label")),
+            None => return Err("Unexpected end of a label".to_string()),
+        };
+        self.depth -= 1;
+        Ok(expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_goal, to_goal};
+    use crate::HoaAutomaton;
+
+    const BUCHI: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<structure label-on="Transition" type="FiniteStateAutomaton">
+    <name>GF a</name>
+    <description/>
+    <alphabet type="Propositional">
+        <prop>a</prop>
+        <prop>b</prop>
+    </alphabet>
+    <stateSet>
+        <state sid="3"><y>10</y><x>20</x></state>
+        <state sid="7"/>
+    </stateSet>
+    <transitionSet complete="false">
+        <transition tid="0">
+            <from>3</from>
+            <to>7</to>
+            <read>a ~b</read>
+        </transition>
+        <transition tid="1">
+            <from>7</from>
+            <to>3</to>
+            <read>True</read>
+        </transition>
+    </transitionSet>
+    <initialStateSet>
+        <stateID>3</stateID>
+    </initialStateSet>
+    <acc type="Buchi">
+        <stateID>7</stateID>
+    </acc>
+</structure>"#;
+
+    #[test]
+    fn buchi_round_trip() {
+        let aut = from_goal(BUCHI).unwrap();
+        let expected = HoaAutomaton::try_from(
+            r#"HOA: v1
+name: "GF a"
+States: 2
+Start: 0
+AP: 2 "a" "b"
+acc-name: Buchi
+Acceptance: 1 Inf(0)
+properties: trans-labels explicit-labels state-acc
+--BODY--
+State: 0
+[0 & !1] 1
+State: 1
+[t] 0 {0}
+--END--"#,
+        )
+        .unwrap();
+        assert_eq!(aut, expected);
+        let written = to_goal(&aut).unwrap();
+        assert!(written.contains("<alphabet type=\"Propositional\">"));
+        assert!(written.contains("<read>a ~b</read>"));
+        assert!(written.contains("<acc type=\"Buchi\">\n    <stateID>1</stateID>\n  </acc>"));
+        assert_eq!(from_goal(&written).unwrap(), aut);
+    }
+
+    #[test]
+    fn other_acceptance_conditions() {
+        let rabin = from_goal(
+            &BUCHI
+                .replace("Propositional", "Boolean")
+                .replace("a ~b", "~(a | b) &amp; True")
+                .replace(
+                    "<acc type=\"Buchi\">\n        <stateID>7</stateID>",
+                    "<acc type=\"Rabin\"><accPair><E><stateID>3</stateID></E>\
+                     <F><stateID>7</stateID></F></accPair>",
+                ),
+        )
+        .unwrap();
+        assert_eq!(rabin.aps(), ["a", "b"]);
+        assert_eq!(rabin.acceptance().1.to_string(), "(Fin(0) & Inf(1))");
+        assert_eq!(
+            rabin.body()[0].edges()[0].label().to_string(),
+            "[!(0 | 1) & t]"
+        );
+        let written = to_goal(&rabin).unwrap();
+        assert!(written.contains("<alphabet type=\"Boolean\">"));
+        assert!(written.contains("<read>~(a | b) &amp; True</read>"));
+        assert!(written.contains("<E>\n        <stateID>0</stateID>\n      </E>"));
+        assert_eq!(from_goal(&written).unwrap(), rabin);
+
+        for (hoa, kind) in [
+            ("1 Fin(0)", "CoBuchi"),
+            ("4 (Fin(0) | Inf(1)) & (Fin(2) | Inf(3))", "Streett"),
+            ("3 Inf(0) | (Fin(1) & Inf(2))", "Parity"),
+        ] {
+            let aut = HoaAutomaton::try_from(
+                format!(
+                    "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAcceptance: {hoa}\n--BODY--\n\
+                     State: 0\n[0] 0 {{0}}\n[!0] 0 {{0}}\n--END--"
+                )
+                .as_str(),
+            )
+            .unwrap();
+            let written = to_goal(&aut).unwrap();
+            assert!(
+                written.contains(&format!("<acc type=\"{kind}\">")),
+                "{written}"
+            );
+            let read = from_goal(&written).unwrap();
+            assert_eq!(read.body(), aut.body());
+        }
+    }
+
+    #[test]
+    fn undeclared_propositions() {
+        let aut = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 4 \"a\" \"p2\" \"b\" \"c\"\nAcceptance: 1 Inf(0)\n\
+             --BODY--\nState: 0 {0}\n[0 & !2] 0\n[3] 0\n--END--",
+        )
+        .unwrap();
+        let mut header = aut.header().clone();
+        for item in header.iter_mut() {
+            if let crate::HeaderItem::AP(names) = item {
+                names.truncate(2);
+            }
+        }
+        let aut = HoaAutomaton::from_parts(header, aut.body().clone());
+        let written = to_goal(&aut).unwrap();
+        assert!(written.contains(
+            "<prop>a</prop>\n    <prop>p2</prop>\n    <prop>p2_</prop>\n    <prop>p3</prop>"
+        ));
+        assert!(written.contains("<read>a ~p2_</read>"));
+        let read = from_goal(&written).unwrap();
+        assert_eq!(read.aps(), ["a", "p2", "p2_", "p3"]);
+        assert_eq!(read.body(), aut.body());
+    }
+
+    #[test]
+    fn unsupported_and_malformed() {
+        let generalized = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 1\nStart: 0\nAcceptance: 2 Inf(0) & Inf(1)\n--BODY--\nState: 0\n[t] 0\n--END--",
+        )
+        .unwrap();
+        assert!(to_goal(&generalized).is_err());
+        let transition_based = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAcceptance: 1 Inf(0)\n--BODY--\n\
+             State: 0\n[0] 0 {0}\n[!0] 0\n--END--",
+        )
+        .unwrap();
+        assert!(to_goal(&transition_based).is_err());
+
+        assert!(from_goal("<structure").is_err());
+        assert_eq!(
+            from_goal(&BUCHI.replace("<to>7</to>", "<to>8</to>")),
+            Err("There is no state 8".to_string())
+        );
+        assert_eq!(
+            from_goal(&BUCHI.replace("a ~b", "a ~c")),
+            Err("The proposition \"c\" is not in the alphabet".to_string())
+        );
+        let deep = BUCHI.replace("Propositional", "Boolean").replace(
+            "a ~b",
+            &format!("{}a{}", "(".repeat(1000), ")".repeat(1000)),
+        );
+        assert!(from_goal(&deep).is_err());
+    }
+}
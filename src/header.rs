@@ -3,7 +3,9 @@ use std::ops::{Deref, DerefMut};
 use chumsky::prelude::*;
 
 use crate::{
+    error::quirk,
     format::{AtomicProposition, StateConjunction},
+    lexer::Span,
     value, AbstractLabelExpression, AcceptanceCondition, AcceptanceInfo, AcceptanceName, AliasName,
     Id, Property, Token,
 };
@@ -12,8 +14,10 @@ use crate::{
 /// element, see the [HOA format specification](https://adl.github.io/hoaf/).
 /// The multiplicity of each element is given in parenthesis.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HeaderItem {
     /// The version of the HOA format.
+    #[cfg_attr(feature = "serde", serde(rename = "HOA"))]
     Version(String),
     /// (0|1) State header, gives the number of states in the automaton.
     States(Id),
@@ -27,13 +31,21 @@ pub enum HeaderItem {
     /// (1) Gives the acceptance condition of the automaton.
     Acceptance(Id, AcceptanceCondition),
     /// (>=0) Gives the acceptance sets of the automaton.
+    #[cfg_attr(feature = "serde", serde(rename = "acc-name"))]
     AcceptanceName(AcceptanceName, Vec<AcceptanceInfo>),
     /// (0|1) Correspond to tool name and optional version number.
+    #[cfg_attr(feature = "serde", serde(rename = "tool"))]
     Tool(String, Option<String>),
     /// (0|1) Correspond to the name of the automaton.
+    #[cfg_attr(feature = "serde", serde(rename = "name"))]
     Name(String),
     /// (>=0) Gives the properties of the automaton.
+    #[cfg_attr(feature = "serde", serde(rename = "properties"))]
     Properties(Vec<Property>),
+    /// (0|1) Since v1.1, gives the indices of the atomic propositions that are controlled
+    /// by the system, the remaining ones are controlled by the environment.
+    #[cfg_attr(feature = "serde", serde(rename = "controllable-AP"))]
+    ControllableAP(Vec<Id>),
 }
 
 impl HeaderItem {
@@ -69,6 +81,7 @@ impl HeaderItem {
     }
 }
 
+#[allow(clippy::result_large_err)]
 fn item() -> impl Parser<Token, HeaderItem, Error = Simple<Token>> {
     let states = just(Token::Header("States".to_string()))
         .ignore_then(value::integer())
@@ -121,12 +134,31 @@ fn item() -> impl Parser<Token, HeaderItem, Error = Simple<Token>> {
 
     let properties = just(Token::Header("properties".to_string()))
         .ignore_then(
-            value::identifier()
-                .try_map(|p, span| Property::try_from(p).map_err(|err| Simple::custom(span, err)))
-                .repeated()
-                .at_least(1),
+            just(Token::Op('!'))
+                .or_not()
+                .then(value::identifier())
+                .validate(|(negated, property), span, emit| {
+                    let property = match property.as_str() {
+                        "terminatl" => {
+                            emit(quirk(span, "terminatl is a misspelling of terminal"));
+                            Property::Terminal
+                        }
+                        _ => Property::try_from(property)
+                            .map_err(|err| emit(quirk(span, err)))
+                            .ok()?,
+                    };
+                    Some(match negated {
+                        Some(_) => Property::Negated(Box::new(property)),
+                        None => property,
+                    })
+                })
+                .repeated(),
         )
-        .map(HeaderItem::Properties);
+        .map(|properties| HeaderItem::Properties(properties.into_iter().flatten().collect()));
+
+    let controllable_aps = just(Token::Header("controllable-AP".to_string()))
+        .ignore_then(value::integer().repeated())
+        .map(HeaderItem::ControllableAP);
 
     chumsky::primitive::choice((
         states,
@@ -138,11 +170,33 @@ fn item() -> impl Parser<Token, HeaderItem, Error = Simple<Token>> {
         name,
         tool,
         properties,
+        controllable_aps,
     ))
 }
 
+/// Returns the tokens at which parsing resumes after a header item could not be parsed,
+/// which are the names of all header items and the start of the body.
+fn resume_tokens() -> [Token; 12] {
+    let header = |name: &str| Token::Header(name.to_string());
+    [
+        header("HOA"),
+        header("States"),
+        header("Start"),
+        header("AP"),
+        header("Alias"),
+        header("Acceptance"),
+        header("acc-name"),
+        header("tool"),
+        header("name"),
+        header("properties"),
+        header("controllable-AP"),
+        Token::BodyStart,
+    ]
+}
+
 /// Represents the header of a HOA file, consists of a set of [`HeaderItem`]s.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header(Vec<HeaderItem>);
 
 impl From<Vec<HeaderItem>> for Header {
@@ -164,12 +218,32 @@ impl<'a> IntoIterator for &'a Header {
 impl Header {
     /// Construts a new header parser.
     pub fn parser() -> impl Parser<Token, Self, Error = Simple<Token>> {
+        Self::spanned_parser().map(|(header, _)| header)
+    }
+
+    /// Constructs a header parser that additionally returns the span of each item. If an
+    /// item cannot be parsed, the error is recorded and parsing resumes at the next item.
+    pub(crate) fn spanned_parser() -> impl Parser<Token, (Self, Vec<Span>), Error = Simple<Token>> {
         let version = just(Token::Header("HOA".to_string()))
             .ignore_then(value::identifier())
             .map(HeaderItem::Version);
+        let item = item()
+            .map_with_span(|item, span| Some((item, span)))
+            .recover_with(skip_until(resume_tokens(), |_| None).skip_start());
         version
-            .then(item().repeated())
-            .map(|(version, headers)| Header(std::iter::once(version).chain(headers).collect()))
+            .map_with_span(|item, span| (item, span))
+            .then(
+                filter(|token| *token != Token::BodyStart)
+                    .rewind()
+                    .ignore_then(item)
+                    .repeated(),
+            )
+            .map(|(version, headers)| {
+                let (items, spans) = std::iter::once(version)
+                    .chain(headers.into_iter().flatten())
+                    .unzip();
+                (Header(items), spans)
+            })
     }
 
     /// Constructs a new header from a vector of header items.
@@ -219,7 +293,9 @@ mod tests {
     pub fn process_header(input: &str) -> Result<Vec<HeaderItem>, String> {
         use chumsky::Stream;
 
-        use crate::{build_error_report, lexer};
+        use itertools::Itertools;
+
+        use crate::{error::parse_errors, lexer};
 
         let with_hoa = format!("HOA: v1\n{}", input);
 
@@ -234,18 +310,13 @@ mod tests {
                 let out = ast.unwrap();
                 Ok(out.0)
             } else {
-                Err(build_error_report(
-                    input,
-                    errs.into_iter()
-                        .map(|err| err.map(|c| c.to_string()))
-                        .chain(parse_errs.into_iter().map(|err| err.map(|c| c.to_string()))),
-                ))
+                Err(parse_errors(input, errs)
+                    .into_iter()
+                    .chain(parse_errors(input, parse_errs))
+                    .join("\n"))
             }
         } else {
-            Err(build_error_report(
-                input,
-                errs.into_iter().map(|err| err.map(|c| c.to_string())),
-            ))
+            Err(parse_errors(input, errs).into_iter().join("\n"))
         }
     }
 
@@ -275,7 +346,14 @@ mod tests {
             ],
         );
         assert_fails(r#"properties: trans-labels statelabels"#);
-        assert_fails(r#"properties: "#);
+        // the list of properties may be empty
+        assert_header(
+            r#"properties: "#,
+            &[
+                HeaderItem::Version("v1".to_string()),
+                HeaderItem::Properties(vec![]),
+            ],
+        );
     }
 
     #[test]
@@ -294,6 +372,24 @@ mod tests {
         assert_fails("tool: ltl-translate \"1.2-alpha\"");
     }
 
+    #[test]
+    fn escaped_strings() {
+        assert_header(
+            r#"
+                tool: "c:\\tools\\ltl2tgba" "2.\1"
+                name: "a \"quoted\" name"
+                AP: 2 "a\"b" "\\"
+            "#,
+            &[
+                HeaderItem::Version("v1".to_string()),
+                HeaderItem::Tool(r"c:\tools\ltl2tgba".to_string(), Some(r"2.\1".to_string())),
+                HeaderItem::Name(r#"a "quoted" name"#.to_string()),
+                HeaderItem::AP(vec![r#"a"b"#.to_string(), r"\".to_string()]),
+            ],
+        );
+        assert_fails(r#"name: "unterminated \""#);
+    }
+
     #[test]
     fn ariadne() {
         assert_header(
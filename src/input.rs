@@ -1,35 +1,746 @@
-use chumsky::{Parser, Stream};
+use std::collections::HashMap;
 
-use crate::{build_error_report, lexer, FromHoaError, HoaAutomaton};
+use chumsky::{error::Simple, prelude::end, Parser, Stream};
 
+use crate::{
+    error::{self, is_quirk, parse_errors, quirk},
+    fast,
+    lexer::{self, Token},
+    value, AbstractLabelExpression, FromHoaError, HeaderItem, HoaAutomaton, Id, ParseError,
+    ParseErrorKind, Property, Span,
+};
+
+/// The deepest nesting of parentheses in label expressions and acceptance conditions that
+/// is parsed, deeper inputs are rejected instead of exhausting the stack. The parser needs
+/// up to 48 KiB of stack per level in unoptimised builds, this stays well within the 2 MiB
+/// that threads get by default.
+pub(crate) const MAX_NESTING: usize = 24;
+
+/// The most acceptance sets that the readers of other formats accept. They build their
+/// acceptance conditions from a count in the input, this bounds the size of the result.
+pub(crate) const MAX_ACCEPTANCE_SETS: usize = 4096;
+
+/// The most tokens that the definition of an alias may expand to. An alias can refer to
+/// aliases that are defined before it, this keeps the expansion linear in the input.
+pub(crate) const MAX_ALIAS_SIZE: usize = 1 << 16;
+
+/// Controls how closely the input has to follow the
+/// [HOA format specification](https://adl.github.io/hoaf/).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// If set, every deviation from the format is an error. Otherwise, the following quirks
+    /// of older tools are accepted and reported as warnings:
+    /// - unknown values in `properties:`, which are dropped,
+    /// - the misspelled property `terminatl`,
+    /// - a missing `States:` header in a v1 automaton, which is then inferred from the body
+    ///   as it is since v1.1,
+    /// - input after `--END--`, which is ignored,
+    /// - features of HOA v1.1 in a v1 automaton,
+    /// - version strings like `v1.2`, which are read as v1.1.
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self { strict: true }
+    }
+}
+
+impl ParseOptions {
+    /// Options that accept the quirks of older tools with warnings.
+    pub fn lenient() -> Self {
+        Self { strict: false }
+    }
+}
+
+/// Parses a single HOA automaton, fails with all errors that were encountered. References
+/// to aliases in labels are replaced by their definitions, the `Alias:` items are kept.
 pub fn from_hoa(value: &str) -> Result<HoaAutomaton, FromHoaError> {
+    from_hoa_with(value, ParseOptions::default()).map(|(automaton, _)| automaton)
+}
+
+/// Parses a single HOA automaton with the given options. On success, the automaton is
+/// returned together with warnings about the quirks that were accepted.
+pub fn from_hoa_with(
+    value: &str,
+    options: ParseOptions,
+) -> Result<(HoaAutomaton, Vec<ParseError>), FromHoaError> {
+    if value.contains("--ABORT--") {
+        return Err(FromHoaError::Abort);
+    }
+    let parsed = parse(value, options);
+    if !parsed.lexer_errors.is_empty() {
+        return Err(FromHoaError::LexerError(parsed.lexer_errors));
+    }
+    if let Some(version) = parsed.unsupported_version {
+        return Err(FromHoaError::UnsupportedVersion(version));
+    }
+    match parsed.automaton {
+        Some(automaton) if parsed.errors.is_empty() => Ok((automaton, parsed.warnings)),
+        _ => Err(FromHoaError::ParserError(parsed.errors)),
+    }
+}
+
+/// Parses a single HOA automaton like [`from_hoa`], but considerably faster and with far
+/// fewer allocations, which pays off for large inputs. The resulting automaton does not
+/// record [`crate::Spans`]. Falls back to [`from_hoa`] if the input contains an error, so
+/// the same errors are reported.
+pub fn from_hoa_fast(value: &str) -> Result<HoaAutomaton, FromHoaError> {
     if value.contains("--ABORT--") {
         return Err(FromHoaError::Abort);
     }
-    let input = value;
     let start = std::time::Instant::now();
-    let tokens = lexer::tokenizer()
-        .parse(input)
-        .map_err(|error_list| {
-            build_error_report(
-                input,
-                error_list.into_iter().map(|err| err.map(|c| c.to_string())),
-            )
-        })
-        .map_err(FromHoaError::LexerError)?;
+    if let Ok(mut automaton) = fast::parse(value) {
+        let mut errors = vec![];
+        if check_header(&mut automaton, &mut errors).is_none() {
+            check_ids(&automaton, &mut errors);
+        }
+        if errors.is_empty() {
+            tracing::info!("Fast parsing took {}µs", start.elapsed().as_micros());
+            return Ok(automaton);
+        }
+    }
+    from_hoa(value)
+}
+
+/// Parses as much of a HOA automaton as possible and returns it together with all errors
+/// that were encountered, so that a broken file can be fixed in one pass. Parsing resumes
+/// after a broken header item, state or edge, which is then missing from the returned
+/// automaton. No automaton is returned if the input does not contain a `HOA:` line and the
+/// start of a body. An `--ABORT--` ends the body like `--END--`.
+pub fn from_hoa_partial(value: &str) -> (Option<HoaAutomaton>, Vec<ParseError>) {
+    let (automaton, errors, _) = from_hoa_partial_with(value, ParseOptions::default());
+    (automaton, errors)
+}
+
+/// Parses as much of a HOA automaton as possible with the given options, like
+/// [`from_hoa_partial`]. Returns the errors and the warnings about the quirks that were
+/// accepted separately.
+pub fn from_hoa_partial_with(
+    value: &str,
+    options: ParseOptions,
+) -> (Option<HoaAutomaton>, Vec<ParseError>, Vec<ParseError>) {
+    let parsed = parse(value, options);
+    let mut errors = parsed.lexer_errors;
+    errors.extend(parsed.errors);
+    (parsed.automaton, errors, parsed.warnings)
+}
+
+/// The result of parsing with error recovery.
+struct Parsed {
+    automaton: Option<HoaAutomaton>,
+    unsupported_version: Option<String>,
+    lexer_errors: Vec<ParseError>,
+    errors: Vec<ParseError>,
+    warnings: Vec<ParseError>,
+}
+
+/// Tokenizes and parses the input with error recovery, quirks are errors in strict mode and
+/// warnings otherwise.
+fn parse(input: &str, options: ParseOptions) -> Parsed {
+    let start = std::time::Instant::now();
+    let (tokens, lexer_errors) = lexer::tokenizer().parse_recovery(input);
+    let lexer_errors = parse_errors(input, lexer_errors);
     tracing::info!("Tokenization took {}µs", start.elapsed().as_micros());
+    let Some(tokens) = tokens else {
+        return Parsed {
+            automaton: None,
+            unsupported_version: None,
+            lexer_errors,
+            errors: vec![],
+            warnings: vec![],
+        };
+    };
+
+    let tokens = match expand_aliases(tokens) {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            return Parsed {
+                automaton: None,
+                unsupported_version: None,
+                lexer_errors,
+                errors: parse_errors(input, [error]),
+                warnings: vec![],
+            }
+        }
+    };
+    if let Some(span) = too_deep(&tokens) {
+        let error: Simple<Token> = error::custom(
+            ParseErrorKind::NestingTooDeep,
+            span,
+            format!("Expressions are nested deeper than {MAX_NESTING} levels"),
+        );
+        return Parsed {
+            automaton: None,
+            unsupported_version: None,
+            lexer_errors,
+            errors: parse_errors(input, [error]),
+            warnings: vec![],
+        };
+    }
 
     let length = input.chars().count();
     let start = std::time::Instant::now();
-    let out = HoaAutomaton::parser()
+    let (mut automaton, mut errors) = HoaAutomaton::parser()
+        .parse_recovery(Stream::from_iter(length..length + 1, tokens.into_iter()));
+    tracing::info!("Actual parsing took {}µs", start.elapsed().as_micros());
+    let unsupported_version = automaton
+        .as_mut()
+        .and_then(|automaton| check_header(automaton, &mut errors));
+    if let (Some(automaton), None) = (&automaton, &unsupported_version) {
+        check_ids(automaton, &mut errors);
+    }
+
+    errors.sort_by_key(|error| error.span().start);
+    let (warnings, errors) = errors
+        .into_iter()
+        .partition::<Vec<_>, _>(|error| !options.strict && is_quirk(error));
+    Parsed {
+        automaton,
+        unsupported_version,
+        lexer_errors,
+        errors: parse_errors(input, errors),
+        warnings: parse_errors(input, warnings),
+    }
+}
+
+/// Parses a label expression in HOA syntax, every token is passed through `rename` before
+/// it is parsed.
+pub(crate) fn parse_label(
+    label: &str,
+    rename: impl FnMut((Token, Span)) -> (Token, Span),
+) -> Result<AbstractLabelExpression, Vec<ParseError>> {
+    let tokens = lexer::tokenizer()
+        .then_ignore(end())
+        .parse(label)
+        .map_err(|errors| parse_errors(label, errors))?;
+    if let Some(span) = too_deep(&tokens) {
+        let error: Simple<Token> = error::custom(
+            ParseErrorKind::NestingTooDeep,
+            span,
+            format!("The label is nested deeper than {MAX_NESTING} levels"),
+        );
+        return Err(parse_errors(label, [error]));
+    }
+    let tokens: Vec<_> = tokens.into_iter().map(rename).collect();
+    let length = label.chars().count();
+    value::label_expression()
+        .then_ignore(end())
         .parse(Stream::from_iter(length..length + 1, tokens.into_iter()))
-        .map_err(|error_list| {
-            build_error_report(
-                input,
-                error_list.into_iter().map(|err| err.map(|c| c.to_string())),
+        .map_err(|errors| parse_errors(label, errors))
+}
+
+/// Checks the version of a parsed automaton and that it only uses features of that version.
+/// Infers the number of states if the `States:` header is missing. Returns the version if it
+/// is not supported.
+fn check_header(automaton: &mut HoaAutomaton, errors: &mut Vec<Simple<Token>>) -> Option<String> {
+    let spans = automaton.spans().cloned().unwrap_or_default();
+    let span = |index: usize| spans.header_item(index).cloned().unwrap_or(0..0);
+    let version = automaton.version();
+    let v1_1 = match version.as_str() {
+        "v1" => false,
+        "v1.1" => true,
+        _ if version.starts_with("v1.") => {
+            errors.push(quirk(
+                span(0),
+                format!("HOA version {version} is read as v1.1"),
+            ));
+            true
+        }
+        _ => {
+            errors.push(Simple::custom(
+                span(0),
+                format!("Unsupported HOA version {version}"),
+            ));
+            return Some(version);
+        }
+    };
+
+    // the Acceptance header has to occur exactly once, these headers at most once
+    let mut seen = vec![];
+    for (index, item) in automaton.header().iter().enumerate() {
+        let name = match item {
+            HeaderItem::Version(_) => "HOA",
+            HeaderItem::States(_) => "States",
+            HeaderItem::AP(_) => "AP",
+            HeaderItem::Acceptance(..) => "Acceptance",
+            HeaderItem::AcceptanceName(..) => "acc-name",
+            HeaderItem::Tool(..) => "tool",
+            HeaderItem::Name(_) => "name",
+            HeaderItem::ControllableAP(_) => "controllable-AP",
+            _ => continue,
+        };
+        if seen.contains(&name) {
+            errors.push(Simple::custom(
+                span(index),
+                format!("The {name} header may only occur once"),
+            ));
+        }
+        seen.push(name);
+    }
+    if !seen.contains(&"Acceptance") {
+        let header_span = span(0).start..spans.header.last().map_or(0, |span| span.end);
+        errors.push(Simple::custom(
+            header_span,
+            "The Acceptance header is missing",
+        ));
+    }
+
+    if !v1_1 {
+        for (index, item) in automaton.header().iter().enumerate() {
+            let feature = match item {
+                HeaderItem::ControllableAP(_) => "controllable-AP".to_string(),
+                HeaderItem::Properties(properties) => {
+                    match properties.iter().find(|property| property.requires_v1_1()) {
+                        Some(Property::Negated(_)) => "Negated properties".to_string(),
+                        Some(property) => property.to_string(),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            errors.push(quirk(span(index), format!("{feature} requires HOA v1.1")));
+        }
+    }
+
+    // since v1.1, the number of states may be omitted
+    if automaton.header().count_states().is_none() {
+        if !v1_1 {
+            let header_span = span(0).start..spans.header.last().map_or(0, |span| span.end);
+            errors.push(quirk(header_span, "The States header is missing"));
+        }
+        automaton.add_header_item(HeaderItem::States(automaton.body().len() as Id));
+    }
+    None
+}
+
+/// Replaces every reference to a defined alias by its definition in parentheses, the
+/// inserted tokens take the span of the reference. An alias can refer to aliases that are
+/// defined before it, references to undefined aliases are left for the parser to report.
+/// Fails if a definition expands to more than [`MAX_ALIAS_SIZE`] tokens.
+#[allow(clippy::result_large_err)]
+fn expand_aliases(tokens: Vec<(Token, Span)>) -> Result<Vec<(Token, Span)>, Simple<Token>> {
+    let mut definitions: HashMap<String, Vec<Token>> = HashMap::new();
+    let mut expanded: Vec<(Token, Span)> = Vec::with_capacity(tokens.len());
+    // the name and span of the alias that is being defined and its first token
+    let mut defining: Option<(String, Span, usize)> = None;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some((token, span)) = tokens.next() {
+        match token {
+            Token::Header(header) if header == "Alias" => {
+                expanded.push((Token::Header(header), span.clone()));
+                if let Some((Token::Alias(name), _)) = tokens.peek() {
+                    defining = Some((name.clone(), span, expanded.len() + 1));
+                    expanded.extend(tokens.next());
+                }
+            }
+            Token::Alias(name) if definitions.contains_key(&name) => {
+                expanded.push((Token::Paren('('), span.clone()));
+                let definition = definitions[&name].iter().cloned();
+                expanded.extend(definition.map(|token| (token, span.clone())));
+                expanded.push((Token::Paren(')'), span));
+            }
+            token => expanded.push((token, span)),
+        }
+
+        // a definition ends with the next header item or the body
+        if !matches!(
+            tokens.peek(),
+            None | Some((Token::Header(_) | Token::BodyStart, _))
+        ) {
+            continue;
+        }
+        if let Some((name, span, start)) = defining.take() {
+            if expanded.len() - start > MAX_ALIAS_SIZE {
+                let end = expanded.last().map_or(span.end, |(_, last)| last.end);
+                return Err(Simple::custom(
+                    span.start..end,
+                    format!("The alias @{name} expands to more than {MAX_ALIAS_SIZE} tokens"),
+                ));
+            }
+            let definition = expanded[start..].iter().map(|(token, _)| token.clone());
+            definitions.insert(name, definition.collect());
+        }
+    }
+    Ok(expanded)
+}
+
+/// Returns the span of the first parenthesis that is nested deeper than [`MAX_NESTING`], if
+/// any.
+fn too_deep(tokens: &[(Token, Span)]) -> Option<Span> {
+    let mut depth = 0usize;
+    for (token, span) in tokens {
+        match token {
+            Token::Paren('(') if depth == MAX_NESTING => return Some(span.clone()),
+            Token::Paren('(') => depth += 1,
+            Token::Paren(')') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Checks that all states and atomic propositions that are referenced are declared by the
+/// `States:` and `AP:` headers.
+fn check_ids(automaton: &HoaAutomaton, errors: &mut Vec<Simple<Token>>) {
+    let spans = automaton.spans().cloned().unwrap_or_default();
+    let states = automaton.header().count_states();
+    let aps = automaton.header().iter().find_map(|item| match item {
+        HeaderItem::AP(aps) => Some(aps.len()),
+        _ => None,
+    });
+    let state_error = |id: Id, span: Option<&Span>| {
+        states
+            .filter(|states| id as usize >= *states)
+            .map(|states| {
+                error::custom(
+                    ParseErrorKind::IdOutOfRange,
+                    span.cloned().unwrap_or(0..0),
+                    format!("State {id} is out of range, there are {states} states"),
+                )
+            })
+    };
+    let ap_error = |id: Option<Id>, span: Option<&Span>| {
+        let id = id?;
+        aps.filter(|aps| id as usize >= *aps).map(|aps| {
+            error::custom(
+                ParseErrorKind::IdOutOfRange,
+                span.cloned().unwrap_or(0..0),
+                format!("Atomic proposition {id} is out of range, there are {aps} propositions"),
             )
         })
-        .map_err(FromHoaError::ParserError);
-    tracing::info!("Actual parsing took {}µs", start.elapsed().as_micros());
-    out
+    };
+
+    for (index, item) in automaton.header().iter().enumerate() {
+        let span = spans.header_item(index);
+        match item {
+            HeaderItem::Start(conjunction) => {
+                errors.extend(conjunction.0.iter().filter_map(|id| state_error(*id, span)))
+            }
+            HeaderItem::Alias(_, expression) => errors.extend(ap_error(max_ap(expression), span)),
+            HeaderItem::ControllableAP(ids) => {
+                errors.extend(ap_error(ids.iter().max().copied(), span))
+            }
+            _ => {}
+        }
+    }
+    for (index, state) in automaton.body().iter().enumerate() {
+        errors.extend(state_error(state.id(), spans.state(index)));
+        for (edge_index, edge) in state.edges().iter().enumerate() {
+            let span = spans.edge(index, edge_index);
+            errors.extend(ap_error(max_ap(&edge.label().0), span));
+            errors.extend(
+                edge.state_conjunction()
+                    .0
+                    .iter()
+                    .filter_map(|id| state_error(*id, span)),
+            );
+        }
+    }
+}
+
+/// Returns the largest atomic proposition that occurs in the expression.
+pub(crate) fn max_ap(expression: &AbstractLabelExpression) -> Option<Id> {
+    match expression {
+        AbstractLabelExpression::Boolean(_) => None,
+        AbstractLabelExpression::Integer(ap) => Some(*ap),
+        AbstractLabelExpression::Negated(inner) => max_ap(inner),
+        AbstractLabelExpression::Conjunction(operands)
+        | AbstractLabelExpression::Disjunction(operands) => {
+            operands.iter().filter_map(max_ap).max()
+        }
+    }
+}
+
+/// Renders an error report with the given message that points at `span` in `input`, for
+/// example at one of the [`crate::Spans`] of a parsed automaton. ANSI colours are used if
+/// `colored` is set.
+#[cfg(feature = "ariadne")]
+pub fn render_diagnostic(input: &str, span: crate::Span, message: &str, colored: bool) -> String {
+    crate::error::render_report(input, span, message, message, colored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        from_hoa, from_hoa_partial, from_hoa_partial_with, from_hoa_with, ParseOptions,
+        MAX_ALIAS_SIZE, MAX_NESTING,
+    };
+    use crate::{FromHoaError, HeaderItem, ParseErrorKind, Property};
+
+    const QUIRKY: &str = r#"HOA: v1
+AP: 1 "a"
+Acceptance: 1 Inf(0)
+properties: trans-labels frobnicated terminatl !weak
+--BODY--
+State: 0
+[0] 0 {0}
+[!0] 1
+State: 1
+[t] 1
+--END--
+trailing output"#;
+
+    #[test]
+    fn reports_all_errors() {
+        let input = r#"HOA: v1
+States: 3
+tool: 1
+AP: 1 "a"
+Acceptance: 1 Inf(0)
+--BODY--
+State: 0
+[0] 1 {0}
+[0 & ] 0
+[!0] 0
+State: 1 }
+[t] 1
+State: 2
+[t] 0
+--END--
+garbage"#;
+        let (automaton, errors) = from_hoa_partial(input);
+        let lines = errors.iter().map(|error| error.line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![3, 9, 11, 16]);
+
+        let automaton = automaton.unwrap();
+        assert_eq!(automaton.header().len(), 4);
+        let states = automaton
+            .body()
+            .iter()
+            .map(|state| (state.id(), state.edges().len()))
+            .collect::<Vec<_>>();
+        assert_eq!(states, vec![(0, 2), (1, 0), (2, 1)]);
+
+        assert_eq!(from_hoa(input).unwrap_err().errors(), errors.as_slice());
+    }
+
+    #[test]
+    fn missing_end() {
+        let (automaton, errors) = from_hoa_partial(
+            "HOA: v1\nStates: 1\nAP: 0\nAcceptance: 0 t\n--BODY--\nState: 0\n[t] 0",
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].expected, vec!["--END--"]);
+        assert_eq!(automaton.unwrap().body().len(), 1);
+    }
+
+    #[test]
+    fn lenient_mode_accepts_quirks() {
+        let (automaton, warnings) = from_hoa_with(QUIRKY, ParseOptions::lenient()).unwrap();
+        let lines = warnings
+            .iter()
+            .map(|warning| warning.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![1, 4, 4, 4, 12]);
+        assert_eq!(automaton.num_states(), Some(2));
+        assert!(automaton.header().contains(&HeaderItem::Properties(vec![
+            Property::TransLabels,
+            Property::Terminal,
+            Property::Negated(Box::new(Property::Weak)),
+        ])));
+        assert_eq!(automaton.body().len(), 2);
+
+        // recovery keeps the quirks apart from the errors
+        let broken = QUIRKY.replace("[!0] 1", "[!0 1");
+        let (automaton, errors, warnings) = from_hoa_partial_with(&broken, ParseOptions::lenient());
+        assert_eq!(automaton.map(|automaton| automaton.body().len()), Some(2));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 8);
+        assert_eq!(warnings.len(), 5);
+        let (_, errors, warnings) = from_hoa_partial_with(&broken, ParseOptions::default());
+        assert_eq!((errors.len(), warnings.len()), (6, 0));
+    }
+
+    #[test]
+    fn strict_mode_rejects_quirks() {
+        let errors = from_hoa(QUIRKY).unwrap_err().errors().to_vec();
+        assert_eq!(errors.len(), 5);
+        assert_eq!(errors[0].message, "The States header is missing");
+        assert_eq!(errors[1].message, "Negated properties requires HOA v1.1");
+        assert_eq!(
+            errors[4].message,
+            "Unexpected trailing input after --END--: \"trailing output\""
+        );
+        let long = format!("{QUIRKY} {}", "x".repeat(100));
+        let errors = from_hoa(&long).unwrap_err().errors().to_vec();
+        assert_eq!(
+            errors[4].message,
+            format!(
+                "Unexpected trailing input after --END--: \"trailing output {}…\"",
+                "x".repeat(24)
+            )
+        );
+    }
+
+    #[test]
+    fn versions() {
+        let input = r#"HOA: v1.1
+AP: 2 "in" "out"
+controllable-AP: 1
+Acceptance: 0 t
+properties: semi-deterministic !complete
+--BODY--
+State: 0
+[0 & 1] 0
+--END--"#;
+        let automaton = from_hoa(input).unwrap();
+        assert_eq!(automaton.num_states(), Some(1));
+        assert_eq!(automaton.controllable_aps(), vec![1]);
+        assert!(automaton.verify().is_ok());
+
+        let (_, warnings) =
+            from_hoa_with(&input.replace("v1.1", "v1.2"), ParseOptions::lenient()).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "HOA version v1.2 is read as v1.1");
+        assert!(from_hoa(&input.replace("v1.1", "v1.2")).is_err());
+
+        // v1.1 features are quirks in v1 automata, other versions are not supported
+        let errors = from_hoa(&input.replace("v1.1", "v1")).unwrap_err();
+        assert_eq!(errors.errors().len(), 3);
+        assert_eq!(
+            from_hoa_with(&input.replace("v1.1", "v2"), ParseOptions::lenient()),
+            Err(FromHoaError::UnsupportedVersion("v2".to_string()))
+        );
+
+        // semi-deterministic only exists since v1.1
+        let v1 = "HOA: v1\nStates: 1\nAcceptance: 0 t\nproperties: semi-deterministic\n\
+                  --BODY--\nState: 0\n--END--";
+        let errors = from_hoa(v1).unwrap_err();
+        assert_eq!(
+            errors.errors()[0].message,
+            "semi-deterministic requires HOA v1.1"
+        );
+        assert!(from_hoa(&v1.replace("v1", "v1.1")).is_ok());
+
+        // dots are only part of version strings, not of other identifiers
+        assert!(from_hoa(&input.replace("AP: 2", "Alias: @a.b 0\nAP: 2")).is_err());
+        assert!(
+            from_hoa(&input.replace("Acceptance: 0 t", "acc-name: all.x\nAcceptance: 0 t"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn ids_out_of_range() {
+        let input = r#"HOA: v1
+States: 2
+Start: 0
+AP: 1 "a"
+Acceptance: 1 Inf(0)
+--BODY--
+State: 0
+[0 & 99999999999] 1 {0}
+[1] 2
+State: 1
+[t] 1 & 4294967295
+--END--"#;
+        let (_, errors) = from_hoa_partial(input);
+        let kinds = errors
+            .iter()
+            .map(|error| (error.line, error.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (8, ParseErrorKind::IdOutOfRange),
+                (8, ParseErrorKind::IntegerOverflow),
+                (9, ParseErrorKind::IdOutOfRange),
+                (9, ParseErrorKind::IdOutOfRange),
+                (11, ParseErrorKind::IdOutOfRange),
+            ]
+        );
+        assert_eq!(
+            errors[1].message,
+            "Integer 99999999999 does not fit into 32 bits"
+        );
+        assert_eq!(
+            errors[2].message,
+            "Atomic proposition 1 is out of range, there are 1 propositions"
+        );
+
+        let duplicated = input.replace("Start: 0", "States: 2");
+        let (_, errors) = from_hoa_partial(&duplicated);
+        assert_eq!(errors[0].message, "The States header may only occur once");
+        assert_eq!(errors[0].kind, ParseErrorKind::Syntax);
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize| {
+            format!(
+                "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAcceptance: 0 t\n--BODY--\n\
+                 State: 0\n[{}0{}] 0\n--END--",
+                "(".repeat(depth),
+                ")".repeat(depth)
+            )
+        };
+        // test threads have the default stack of 2 MiB
+        assert!(from_hoa(&nested(MAX_NESTING)).is_ok());
+        let parity = crate::AcceptanceCondition::parity(MAX_NESTING as u32);
+        let input = format!(
+            "HOA: v1\nStates: 1\nStart: 0\nAcceptance: {} {parity}\n--BODY--\n\
+             State: 0\n[t] 0 {{0}}\n--END--",
+            MAX_NESTING
+        );
+        assert!(from_hoa(&input).is_ok());
+        let Err(FromHoaError::ParserError(errors)) = from_hoa(&nested(MAX_NESTING + 1)) else {
+            panic!("nesting beyond the limit is accepted");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::NestingTooDeep);
+    }
+
+    #[test]
+    fn aliases() {
+        let input = r#"HOA: v1
+States: 1
+Start: 0
+AP: 3 "a" "b" "c"
+Alias: @x 0 | 1
+Alias: @y !@x & 2
+Acceptance: 0 t
+--BODY--
+State: 0
+[@x & 2] 0
+[!@x] 0
+[@y] 0
+--END--"#;
+        let aut = from_hoa(input).unwrap();
+        let labels = aut.body()[0]
+            .edges()
+            .iter()
+            .map(|edge| edge.label().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["[(0 | 1) & 2]", "[!(0 | 1)]", "[!(0 | 1) & 2]"]);
+        assert_eq!(aut.aliases()[1].1.to_string(), "!(0 | 1) & 2");
+
+        let undefined = input.replace("[!@x]", "[!@z]");
+        let Err(FromHoaError::ParserError(errors)) = from_hoa(&undefined) else {
+            panic!("an undefined alias is accepted");
+        };
+        assert_eq!(errors[0].message, "The alias @z is not defined");
+        // an alias has to be defined before it is used
+        let later = input.replace(
+            "Alias: @x 0 | 1\nAlias: @y !@x & 2",
+            "Alias: @y !@x & 2\nAlias: @x 0 | 1",
+        );
+        assert!(from_hoa(&later).is_err());
+
+        // every alias doubles the size of the previous one
+        let doubling = (1..=16)
+            .map(|i| format!("Alias: @a{i} @a{} | @a{}\n", i - 1, i - 1))
+            .collect::<String>();
+        let input = format!(
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAlias: @a0 0\n{doubling}\
+             Acceptance: 0 t\n--BODY--\nState: 0\n[@a16] 0\n--END--"
+        );
+        let Err(FromHoaError::ParserError(errors)) = from_hoa(&input) else {
+            panic!("an alias that expands to too many tokens is accepted");
+        };
+        assert_eq!(
+            errors[0].message,
+            format!("The alias @a14 expands to more than {MAX_ALIAS_SIZE} tokens")
+        );
+    }
 }
@@ -0,0 +1,630 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    fmt::Display,
+};
+
+use biodivine_lib_bdd::Bdd;
+use itertools::Itertools;
+
+use crate::{
+    subset::subset_construction,
+    symbolic::{declared_aps, Symbolic, SymbolicEdge},
+    AcceptanceAtom, AcceptanceCondition, AtomicProposition, HoaAutomaton, HoaBool, Id,
+};
+
+/// A single letter of a word, it assigns a truth value to every atomic proposition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Letter(pub Vec<(AtomicProposition, bool)>);
+
+/// An ultimately periodic word `prefix cycle cycle cycle ...`, the cycle is never empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lasso {
+    /// The finite prefix of the word.
+    pub prefix: Vec<Letter>,
+    /// The part of the word that is repeated infinitely often.
+    pub cycle: Vec<Letter>,
+}
+
+/// The outcome of comparing the languages of two automata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    /// The compared property holds.
+    Holds,
+    /// The compared property does not hold, the contained word witnesses this.
+    Counterexample(Lasso),
+}
+
+impl Comparison {
+    /// Returns true if the compared property holds.
+    pub fn holds(&self) -> bool {
+        matches!(self, Comparison::Holds)
+    }
+
+    /// Returns the counterexample, if the compared property does not hold.
+    pub fn counterexample(&self) -> Option<&Lasso> {
+        match self {
+            Comparison::Holds => None,
+            Comparison::Counterexample(word) => Some(word),
+        }
+    }
+}
+
+/// Searches for a word that is accepted by the given automaton, which may not use
+/// universal branching. Returns `None` if the language of the automaton is empty.
+pub fn accepted_word(aut: &HoaAutomaton) -> Result<Option<Lasso>, String> {
+    let sym = Symbolic::from_automaton(aut)?;
+    if sym.is_alternating() {
+        return Err("Emptiness check does not support universal branching".to_string());
+    }
+    Ok(accepting_lasso(&sym).map(|(prefix, cycle)| to_lasso(&sym, &prefix, &cycle)))
+}
+
+/// Checks whether the language of `a` is included in the language of `b`. Here, `a` may not
+/// use universal branching and `b` has to be deterministic. If `b` is not deterministic
+/// but a safety automaton, it is determinised with [`subset_construction`] first. If the
+/// inclusion does not hold, a word accepted by `a` but not by `b` is returned.
+pub fn is_included_in(a: &HoaAutomaton, b: &HoaAutomaton) -> Result<Comparison, String> {
+    let aps = declared_aps(a)
+        .into_iter()
+        .chain(declared_aps(b))
+        .unique()
+        .collect_vec();
+    let left = Symbolic::from_automaton_over(a, aps.clone())?;
+    if left.is_alternating() {
+        return Err("Language inclusion does not support universal branching".to_string());
+    }
+    let right = deterministic(b, aps)?;
+
+    let product = product_with_complement(&left, &right);
+    Ok(match accepting_lasso(&product) {
+        Some((prefix, cycle)) => Comparison::Counterexample(to_lasso(&product, &prefix, &cycle)),
+        None => Comparison::Holds,
+    })
+}
+
+/// Checks whether `a` and `b` accept the same language by checking inclusion in both
+/// directions, see [`is_included_in`] for the requirements on the automata. The returned
+/// counterexample is accepted by exactly one of the two automata.
+pub fn is_equivalent(a: &HoaAutomaton, b: &HoaAutomaton) -> Result<Comparison, String> {
+    match is_included_in(a, b)? {
+        Comparison::Holds => is_included_in(b, a),
+        counterexample => Ok(counterexample),
+    }
+}
+
+/// Builds a deterministic symbolic automaton over the given atomic propositions.
+fn deterministic(aut: &HoaAutomaton, aps: Vec<String>) -> Result<Symbolic, String> {
+    let sym = Symbolic::from_automaton_over(aut, aps.clone())?;
+    if sym.is_deterministic() {
+        return Ok(sym);
+    }
+    let det = subset_construction(aut)
+        .map_err(|err| format!("Automaton is neither deterministic nor determinisable: {err}"))?;
+    Symbolic::from_automaton_over(&det, aps)
+}
+
+/// Computes the product of `left` with the complement of the deterministic automaton
+/// `right`. Acceptance sets of `right` are shifted behind those of `left`, missing
+/// transitions of `right` lead to a rejecting sink whose edges carry one additional set.
+fn product_with_complement(left: &Symbolic, right: &Symbolic) -> Symbolic {
+    let offset = left.acceptance_sets as Id;
+    let sink_mark = offset + right.acceptance_sets as Id;
+    let condition = left.acceptance.and(
+        right
+            .acceptance
+            .shift(offset)
+            .dual()
+            .or(AcceptanceCondition::id_inf(sink_mark)),
+    );
+    let mut product = left.empty_like(sink_mark as usize + 1, condition);
+
+    let right_start = right.start.first().map(|conjunction| conjunction[0]);
+    let mut ids: HashMap<(Id, Option<Id>), Id> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for q in left.start.iter().flatten() {
+        let pair = (*q, right_start);
+        if let Entry::Vacant(entry) = ids.entry(pair) {
+            let id = *entry.insert(product.add_state(None));
+            product.start.push(vec![id]);
+            queue.push_back(pair);
+        }
+    }
+
+    while let Some((p, q)) = queue.pop_front() {
+        let source = ids[&(p, q)];
+        let successors: Vec<(Bdd, Option<Id>, Vec<Id>)> = match q {
+            Some(q) => {
+                let edges = &right.edges[q as usize];
+                let missing = edges
+                    .iter()
+                    .fold(right.vs.mk_true(), |acc, edge| acc.and_not(&edge.guard));
+                edges
+                    .iter()
+                    .map(|edge| {
+                        let marks = edge.marks.iter().map(|m| m + offset).collect();
+                        (edge.guard.clone(), Some(edge.targets[0]), marks)
+                    })
+                    .chain(std::iter::once((missing, None, vec![])))
+                    .collect()
+            }
+            None => vec![(right.vs.mk_true(), None, vec![sink_mark])],
+        };
+        for edge in &left.edges[p as usize] {
+            for (guard, q_target, marks) in &successors {
+                let guard = edge.guard.and(guard);
+                if guard.is_false() {
+                    continue;
+                }
+                let pair = (edge.targets[0], *q_target);
+                let target = *ids.entry(pair).or_insert_with(|| {
+                    queue.push_back(pair);
+                    product.add_state(None)
+                });
+                product.edges[source as usize].push(SymbolicEdge {
+                    guard,
+                    targets: vec![target],
+                    marks: edge.marks.iter().chain(marks).copied().sorted().collect(),
+                });
+            }
+        }
+    }
+    product
+}
+
+/// Turns the guards along a lasso into concrete letters.
+fn to_lasso(sym: &Symbolic, prefix: &[Bdd], cycle: &[Bdd]) -> Lasso {
+    let letter = |guard: &Bdd| {
+        let valuation = guard
+            .sat_witness()
+            .expect("guards on a lasso are satisfiable");
+        Letter(
+            sym.aps
+                .iter()
+                .zip(sym.vars.iter())
+                .map(|(ap, var)| (ap.clone(), valuation[*var]))
+                .collect(),
+        )
+    };
+    Lasso {
+        prefix: prefix.iter().map(letter).collect(),
+        cycle: cycle.iter().map(letter).collect(),
+    }
+}
+
+/// Searches for an accepting lasso in an automaton without universal branching and
+/// returns the guards along its prefix and its cycle. This works for arbitrary
+/// Emerson-Lei conditions: for each disjunct of the condition in disjunctive normal form,
+/// the edges carrying one of its `Fin` sets are removed and a strongly connected component
+/// containing all of its `Inf` sets is searched.
+pub(crate) fn accepting_lasso(sym: &Symbolic) -> Option<(Vec<Bdd>, Vec<Bdd>)> {
+    let (condition, marks) = positive_marks(sym);
+    let reachable = sym.reachable();
+
+    for (fin, inf) in dnf(&condition) {
+        let allowed = |q: usize, i: usize| {
+            !sym.edges[q][i].guard.is_false() && !marks[q][i].iter().any(|m| fin.contains(m))
+        };
+        for component in sccs(sym, &reachable, allowed) {
+            let inside = |q: usize, i: usize| {
+                allowed(q, i) && component.contains(&(sym.edges[q][i].targets[0] as usize))
+            };
+            let seen = component
+                .iter()
+                .flat_map(|q| {
+                    (0..sym.edges[*q].len())
+                        .filter(move |i| inside(*q, *i))
+                        .map(move |i| (*q, i))
+                })
+                .collect_vec();
+            if seen.is_empty()
+                || !inf
+                    .iter()
+                    .all(|m| seen.iter().any(|(q, i)| marks[*q][*i].contains(m)))
+            {
+                continue;
+            }
+
+            let initial = sym.start.iter().map(|c| c[0] as usize).collect_vec();
+            let (prefix, entry) = path(sym, &initial, |_, _| true, |q| component.contains(&q))
+                .expect("component is reachable");
+
+            let mut cycle = vec![];
+            let mut current = entry;
+            let requirements: Vec<Option<Id>> = if inf.is_empty() {
+                vec![None]
+            } else {
+                inf.iter().copied().map(Some).collect()
+            };
+            for requirement in requirements {
+                let (steps, last) = path(sym, &[current], inside, |q| {
+                    (0..sym.edges[q].len()).any(|i| {
+                        inside(q, i) && requirement.is_none_or(|m| marks[q][i].contains(&m))
+                    })
+                })
+                .expect("requirement is met inside the component");
+                cycle.extend(steps);
+                let i = (0..sym.edges[last].len())
+                    .find(|i| {
+                        inside(last, *i) && requirement.is_none_or(|m| marks[last][*i].contains(&m))
+                    })
+                    .unwrap();
+                cycle.push(sym.edges[last][i].guard.clone());
+                current = sym.edges[last][i].targets[0] as usize;
+            }
+            let (steps, _) = path(sym, &[current], inside, |q| q == entry)
+                .expect("component is strongly connected");
+            cycle.extend(steps);
+            return Some((prefix, cycle));
+        }
+    }
+    None
+}
+
+/// Replaces every negative atom `x` in the acceptance condition by a fresh acceptance set
+/// that contains exactly the edges which are not in `x`. Returns the resulting condition
+/// together with the acceptance sets of each edge.
+fn positive_marks(sym: &Symbolic) -> (AcceptanceCondition, Vec<Vec<Vec<Id>>>) {
+    fn negative_atoms(condition: &AcceptanceCondition, out: &mut Vec<Id>) {
+        match condition {
+            AcceptanceCondition::Fin(AcceptanceAtom::Negative(id))
+            | AcceptanceCondition::Inf(AcceptanceAtom::Negative(id)) => out.push(*id),
+            AcceptanceCondition::And(left, right) | AcceptanceCondition::Or(left, right) => {
+                negative_atoms(left, out);
+                negative_atoms(right, out);
+            }
+            _ => {}
+        }
+    }
+    fn rename(condition: &AcceptanceCondition, fresh: &HashMap<Id, Id>) -> AcceptanceCondition {
+        let atom = |atom: &AcceptanceAtom| match atom {
+            AcceptanceAtom::Negative(id) => AcceptanceAtom::Positive(fresh[id]),
+            positive => positive.clone(),
+        };
+        match condition {
+            AcceptanceCondition::Fin(a) => AcceptanceCondition::Fin(atom(a)),
+            AcceptanceCondition::Inf(a) => AcceptanceCondition::Inf(atom(a)),
+            AcceptanceCondition::And(left, right) => rename(left, fresh).and(rename(right, fresh)),
+            AcceptanceCondition::Or(left, right) => rename(left, fresh).or(rename(right, fresh)),
+            boolean => boolean.clone(),
+        }
+    }
+
+    let mut negative = vec![];
+    negative_atoms(&sym.acceptance, &mut negative);
+    let base = sym
+        .edges
+        .iter()
+        .flatten()
+        .flat_map(|edge| edge.marks.iter().map(|m| m + 1))
+        .chain(std::iter::once(sym.acceptance_sets as Id))
+        .max()
+        .unwrap_or(0);
+    let fresh: HashMap<Id, Id> = negative
+        .into_iter()
+        .unique()
+        .enumerate()
+        .map(|(i, id)| (id, base + i as Id))
+        .collect();
+
+    let marks = sym
+        .edges
+        .iter()
+        .map(|edges| {
+            edges
+                .iter()
+                .map(|edge| {
+                    edge.marks
+                        .iter()
+                        .copied()
+                        .chain(
+                            fresh
+                                .iter()
+                                .filter(|(id, _)| !edge.marks.contains(id))
+                                .map(|(_, complement)| *complement),
+                        )
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    (rename(&sym.acceptance, &fresh), marks)
+}
+
+/// Converts a condition without negative atoms into disjunctive normal form, each disjunct
+/// is given by the sets that have to be visited finitely and infinitely often.
+fn dnf(condition: &AcceptanceCondition) -> Vec<(Vec<Id>, Vec<Id>)> {
+    match condition {
+        AcceptanceCondition::Fin(atom) | AcceptanceCondition::Inf(atom) => {
+            let id = match atom {
+                AcceptanceAtom::Positive(id) | AcceptanceAtom::Negative(id) => *id,
+            };
+            if matches!(condition, AcceptanceCondition::Fin(_)) {
+                vec![(vec![id], vec![])]
+            } else {
+                vec![(vec![], vec![id])]
+            }
+        }
+        AcceptanceCondition::And(left, right) => {
+            let right = dnf(right);
+            dnf(left)
+                .into_iter()
+                .cartesian_product(right)
+                .map(|((lf, li), (rf, ri))| {
+                    (
+                        lf.into_iter().chain(rf).unique().collect(),
+                        li.into_iter().chain(ri).unique().collect(),
+                    )
+                })
+                .collect()
+        }
+        AcceptanceCondition::Or(left, right) => dnf(left).into_iter().chain(dnf(right)).collect(),
+        AcceptanceCondition::Boolean(HoaBool(true)) => vec![(vec![], vec![])],
+        AcceptanceCondition::Boolean(HoaBool(false)) => vec![],
+    }
+}
+
+/// Computes the strongly connected components among the given states, only considering
+/// the edges for which `allowed(state, edge index)` holds. Components are returned in
+/// reverse topological order, i.e. a component comes after all components it can reach.
+pub(crate) fn sccs<F: Fn(usize, usize) -> bool>(
+    sym: &Symbolic,
+    states: &[bool],
+    allowed: F,
+) -> Vec<Vec<usize>> {
+    let n = sym.num_states();
+    let mut index = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut counter = 0;
+
+    for root in (0..n).filter(|q| states[*q]) {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        let mut call_stack = vec![(root, 0usize)];
+        while let Some((q, i)) = call_stack.pop() {
+            if i == 0 {
+                index[q] = counter;
+                low[q] = counter;
+                counter += 1;
+                stack.push(q);
+                on_stack[q] = true;
+            }
+            if let Some(j) = (i..sym.edges[q].len()).find(|j| {
+                let target = sym.edges[q][*j].targets[0] as usize;
+                allowed(q, *j)
+                    && states[target]
+                    && (index[target] == usize::MAX || on_stack[target])
+            }) {
+                call_stack.push((q, j + 1));
+                let target = sym.edges[q][j].targets[0] as usize;
+                if index[target] == usize::MAX {
+                    call_stack.push((target, 0));
+                } else {
+                    low[q] = low[q].min(index[target]);
+                }
+                continue;
+            }
+            if low[q] == index[q] {
+                let mut component = vec![];
+                while let Some(p) = stack.pop() {
+                    on_stack[p] = false;
+                    component.push(p);
+                    if p == q {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some((parent, _)) = call_stack.last() {
+                low[*parent] = low[*parent].min(low[q]);
+            }
+        }
+    }
+    components
+}
+
+/// Finds a shortest path from one of the `sources` to a state satisfying `goal`, only
+/// using edges for which `allowed` holds. Returns the guards along the path and its final
+/// state.
+fn path<F, G>(sym: &Symbolic, sources: &[usize], allowed: F, goal: G) -> Option<(Vec<Bdd>, usize)>
+where
+    F: Fn(usize, usize) -> bool,
+    G: Fn(usize) -> bool,
+{
+    let mut parent: HashMap<usize, Option<(usize, usize)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for source in sources {
+        parent.entry(*source).or_insert(None);
+        queue.push_back(*source);
+    }
+    while let Some(q) = queue.pop_front() {
+        if goal(q) {
+            let mut guards = vec![];
+            let mut current = q;
+            while let Some((p, i)) = parent[&current] {
+                guards.push(sym.edges[p][i].guard.clone());
+                current = p;
+            }
+            guards.reverse();
+            return Some((guards, q));
+        }
+        for (i, edge) in sym.edges[q].iter().enumerate() {
+            let target = edge.targets[0] as usize;
+            if allowed(q, i) && !parent.contains_key(&target) {
+                parent.insert(target, Some((q, i)));
+                queue.push_back(target);
+            }
+        }
+    }
+    None
+}
+
+impl Display for Letter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "t");
+        }
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|(ap, value)| if *value {
+                    ap.to_string()
+                } else {
+                    format!("!{ap}")
+                })
+                .join(" & ")
+        )
+    }
+}
+
+impl Display for Lasso {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for letter in &self.prefix {
+            write!(f, "{letter}; ")?;
+        }
+        write!(f, "cycle{{{}}}", self.cycle.iter().join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{subset::subset_construction, HoaAutomaton};
+
+    use super::{accepted_word, is_equivalent, is_included_in, Comparison};
+
+    fn parse(input: &str) -> HoaAutomaton {
+        HoaAutomaton::try_from(input).unwrap()
+    }
+
+    const EVENTUALLY_ALWAYS_A: &str = r#"HOA: v1
+        States: 2
+        Start: 0
+        AP: 1 "a"
+        acc-name: Buchi
+        Acceptance: 1 Inf(0)
+        --BODY--
+        State: 0
+        [t] 0
+        [0] 1
+        State: 1
+        [0] 1 {0}
+        --END--"#;
+
+    const INFINITELY_OFTEN_A: &str = r#"HOA: v1
+        States: 1
+        Start: 0
+        AP: 1 "a"
+        acc-name: Buchi
+        Acceptance: 1 Inf(0)
+        --BODY--
+        State: 0
+        [0] 0 {0}
+        [!0] 0
+        --END--"#;
+
+    const DETERMINISTIC_EVENTUALLY_ALWAYS_A: &str = r#"HOA: v1
+        States: 1
+        Start: 0
+        AP: 1 "a"
+        acc-name: co-Buchi
+        Acceptance: 1 Fin(0)
+        --BODY--
+        State: 0
+        [0] 0
+        [!0] 0 {0}
+        --END--"#;
+
+    #[test]
+    fn emptiness() {
+        let word = accepted_word(&parse(INFINITELY_OFTEN_A)).unwrap().unwrap();
+        assert!(word.cycle.iter().any(|letter| letter.0[0].1));
+
+        let empty = parse(
+            r#"HOA: v1
+            States: 2
+            Start: 0
+            AP: 1 "a"
+            Acceptance: 1 Inf(0) & Fin(0)
+            --BODY--
+            State: 0
+            [0] 1 {0}
+            State: 1
+            [t] 0
+            --END--"#,
+        );
+        assert_eq!(accepted_word(&empty), Ok(None));
+    }
+
+    #[test]
+    fn inclusion() {
+        let fg = parse(EVENTUALLY_ALWAYS_A);
+        let gf = parse(INFINITELY_OFTEN_A);
+        assert_eq!(is_included_in(&fg, &gf), Ok(Comparison::Holds));
+        assert!(is_included_in(&gf, &fg).is_err());
+
+        let verdict = is_included_in(&gf, &parse(DETERMINISTIC_EVENTUALLY_ALWAYS_A)).unwrap();
+        let word = verdict.counterexample().unwrap();
+        assert!(word
+            .cycle
+            .iter()
+            .any(|letter| letter.0 == [("a".to_string(), true)]));
+        assert!(word
+            .cycle
+            .iter()
+            .any(|letter| letter.0 == [("a".to_string(), false)]));
+        assert!(word.to_string().contains("cycle{"));
+    }
+
+    #[test]
+    fn equivalence_over_different_aps() {
+        let fg = parse(EVENTUALLY_ALWAYS_A);
+        let reordered = parse(
+            r#"HOA: v1
+            States: 1
+            Start: 0
+            AP: 2 "b" "a"
+            Acceptance: 1 Fin(0)
+            --BODY--
+            State: 0
+            [1] 0
+            [!1] 0 {0}
+            --END--"#,
+        );
+        assert!(is_equivalent(&fg, &reordered).is_err());
+        assert!(
+            is_equivalent(&parse(DETERMINISTIC_EVENTUALLY_ALWAYS_A), &reordered)
+                .unwrap()
+                .holds()
+        );
+        assert!(is_included_in(&fg, &reordered).unwrap().holds());
+    }
+
+    #[test]
+    fn determinised_safety_automaton() {
+        let nba = parse(
+            r#"HOA: v1
+            States: 3
+            Start: 0
+            AP: 2 "a" "b"
+            Acceptance: 1 Fin(0)
+            --BODY--
+            State: 0
+            [!0] 0
+            [0] 1
+            [0] 0
+            State: 1
+            [1] 0
+            [!1] 2
+            State: 2
+            [t] 2 {0}
+            --END--"#,
+        );
+        let det = subset_construction(&nba).unwrap();
+        assert!(is_equivalent(&nba, &det).unwrap().holds());
+    }
+}
@@ -0,0 +1,591 @@
+//! Conversion from and to the LBTT format of the
+//! [LTL-to-Büchi translator testbench](https://www.tcs.hut.fi/Software/lbtt/), which describes
+//! generalized Büchi automata with state- or transition-based acceptance. Guards are written
+//! in prefix notation, e.g. `& p0 ! "b c"`, over atomic propositions that are named `p0`,
+//! `p1`, ... or by a quoted string.
+//!
+//! An automaton starts with the number of states and the number of acceptance sets, which
+//! is followed by `t` if acceptance is transition-based. Each state is given by its id, a
+//! flag that is 1 if it is initial, for state-based acceptance its acceptance sets
+//! terminated by `-1`, and its transitions terminated by `-1`. A transition consists of the
+//! target, for transition-based acceptance its acceptance sets terminated by `-1`, and the
+//! guard.
+
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::{
+    input::{MAX_ACCEPTANCE_SETS, MAX_NESTING},
+    output::AcceptancePlacement,
+    AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, AcceptanceName,
+    AcceptanceSignature, Body, Edge, Header, HeaderItem, HoaAutomaton, Id, Label, Property, State,
+    StateConjunction,
+};
+
+/// The most nodes that a guard may have. The operators `e` and `^` repeat their operands,
+/// so a guard can otherwise grow exponentially in its depth.
+const MAX_GUARD_SIZE: usize = 1 << 16;
+
+/// Reads an automaton in the LBTT format. The states are numbered in the order in which
+/// they are listed and the atomic propositions in the order of their first occurrence.
+/// Every state that is flagged as initial becomes an initial state. The number of sets
+/// `n` becomes the acceptance condition `Inf(0) & ... & Inf(n - 1)`.
+pub fn from_lbtt(input: &str) -> Result<HoaAutomaton, String> {
+    let mut tokens = Tokens::new(input);
+    let states = tokens.number("the number of states")?;
+    let states = Id::try_from(states)
+        .map_err(|_| format!("line {}: Invalid number of states {states}", tokens.line))?;
+    let (sets, transition_based) = match tokens.next()? {
+        Some((token, line)) => {
+            let (count, suffix) = match token.strip_suffix(['t', 's']) {
+                Some(count) => (count, token.ends_with('t')),
+                None => (token, false),
+            };
+            let count = count.parse::<Id>().map_err(|_| {
+                format!("line {line}: Expected the number of acceptance sets, found {token:?}")
+            })?;
+            if count as usize > MAX_ACCEPTANCE_SETS {
+                return Err(format!(
+                    "line {line}: More than {MAX_ACCEPTANCE_SETS} acceptance sets are not supported"
+                ));
+            }
+            (count, suffix)
+        }
+        None => return Err("Expected the number of acceptance sets".to_string()),
+    };
+
+    let mut aps = vec![];
+    let mut ids = vec![];
+    let mut start = vec![];
+    let mut parsed = vec![];
+    for position in 0..states {
+        let id = tokens.number("a state id")?;
+        if ids.contains(&id) {
+            return Err(format!("line {}: State {id} is listed twice", tokens.line));
+        }
+        ids.push(id);
+        match tokens.number("the initial flag of a state")? {
+            0 => {}
+            1 => start.push(position),
+            flag => return Err(format!("line {}: Invalid initial flag {flag}", tokens.line)),
+        }
+        let state_marks = match transition_based {
+            true => vec![],
+            false => tokens.marks(sets)?,
+        };
+        let mut edges = vec![];
+        loop {
+            let target = tokens.number("a target or -1")?;
+            if target == -1 {
+                break;
+            }
+            let marks = match transition_based {
+                true => tokens.marks(sets)?,
+                false => state_marks.clone(),
+            };
+            let (guard, _) = tokens.guard(&mut aps, 0)?;
+            edges.push((target, guard, marks));
+        }
+        parsed.push(edges);
+    }
+    if let Some((token, line)) = tokens.next()? {
+        return Err(format!(
+            "line {line}: Unexpected {token:?} after the last state"
+        ));
+    }
+
+    let mut body = vec![];
+    for (position, edges) in parsed.into_iter().enumerate() {
+        let edges = edges
+            .into_iter()
+            .map(
+                |(target, guard, marks)| match ids.iter().position(|id| *id == target) {
+                    Some(target) => Ok(Edge::from_parts(
+                        Label(guard),
+                        StateConjunction::singleton(target as Id),
+                        AcceptanceSignature(marks),
+                    )),
+                    None => Err(format!(
+                        "A transition leads to state {target}, which is not listed"
+                    )),
+                },
+            )
+            .collect::<Result<_, _>>()?;
+        body.push(State::from_parts(position as Id, None, edges));
+    }
+
+    let condition = AcceptanceCondition::all((0..sets).map(AcceptanceCondition::id_inf).collect());
+    let name = match sets {
+        1 => HeaderItem::AcceptanceName(AcceptanceName::Buchi, vec![]),
+        _ => HeaderItem::AcceptanceName(
+            AcceptanceName::GeneralizedBuchi,
+            vec![crate::AcceptanceInfo::Int(sets)],
+        ),
+    };
+    let acceptance = match transition_based {
+        true => Property::TransitionAcceptance,
+        false => Property::StateAcceptance,
+    };
+    let mut header = vec![HeaderItem::v1(), HeaderItem::States(states)];
+    header.extend(
+        start
+            .into_iter()
+            .map(|state| HeaderItem::Start(StateConjunction::singleton(state as Id))),
+    );
+    header.push(HeaderItem::AP(aps));
+    header.push(name);
+    header.push(HeaderItem::Acceptance(sets, condition));
+    header.push(HeaderItem::Properties(vec![
+        Property::TransLabels,
+        Property::ExplicitLabels,
+        acceptance,
+    ]));
+    let aut = HoaAutomaton::from_parts(Header::from_vec(header), Body::from(body));
+    aut.verify()?;
+    Ok(aut)
+}
+
+/// Writes the automaton in the LBTT format using an [`LbttWriter`] with default options.
+/// Fails if the automaton cannot be expressed in the format, see [`LbttWriter::write`].
+pub fn to_lbtt(aut: &HoaAutomaton) -> Result<String, String> {
+    let mut writer = LbttWriter::new(vec![]);
+    writer.write(aut).map_err(|err| err.to_string())?;
+    Ok(String::from_utf8(writer.into_inner()).expect("the output is valid UTF-8"))
+}
+
+/// Writes automata in the LBTT format to an [`std::io::Write`]. By default, acceptance is
+/// transition-based.
+#[derive(Debug)]
+pub struct LbttWriter<W> {
+    out: W,
+    acceptance: AcceptancePlacement,
+}
+
+impl<W: Write> LbttWriter<W> {
+    /// Creates a writer with default options.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            acceptance: AcceptancePlacement::default(),
+        }
+    }
+
+    /// Chooses between state- and transition-based acceptance. State-based acceptance is
+    /// only written if all edges leaving each state carry the same acceptance sets.
+    pub fn acceptance(mut self, acceptance: AcceptancePlacement) -> Self {
+        self.acceptance = acceptance;
+        self
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Writes a single automaton. Fails if the underlying writer fails or if the automaton
+    /// cannot be expressed in the format, which is the case unless its acceptance condition
+    /// is a conjunction of `Inf` atoms, it has exactly one initial state or no states at all
+    /// and none of its edges is universal. The sets that occur in the condition are
+    /// renumbered in the order of their occurrence, all other sets are dropped.
+    pub fn write(&mut self, aut: &HoaAutomaton) -> std::io::Result<()> {
+        let invalid = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string())
+        };
+        let mut sets = vec![];
+        if !generalized_buchi_sets(&aut.acceptance().1, &mut sets) {
+            return Err(invalid("LBTT only supports generalized Büchi acceptance"));
+        }
+        let start = aut.start();
+        let initial = match (start.as_slice(), aut.body().is_empty()) {
+            ([], true) => None,
+            ([start], _) => Some(
+                start
+                    .get_singleton()
+                    .ok_or_else(|| invalid("LBTT does not support universal initial states"))?,
+            ),
+            _ => return Err(invalid("LBTT requires exactly one initial state")),
+        };
+        if aut
+            .body()
+            .edges()
+            .any(|(_, edge)| edge.state_conjunction().get_singleton().is_none())
+        {
+            return Err(invalid("LBTT does not support universal edges"));
+        }
+
+        let marks = |edge: &Edge| {
+            edge.acceptance_signature()
+                .iter()
+                .filter_map(|set| sets.iter().position(|s| s == set))
+                .sorted()
+                .dedup()
+                .map(|set| format!("{set} "))
+                .join("")
+        };
+        let state_based = self.acceptance == AcceptancePlacement::State
+            && aut
+                .body()
+                .iter()
+                .all(|state| state.edges().iter().map(marks).all_equal());
+        let aps = aut.aps();
+        let mut lines = vec![format!(
+            "{} {}{}",
+            aut.body().len(),
+            sets.len(),
+            if state_based { "" } else { "t" }
+        )];
+        for state in aut.body() {
+            let flag = u8::from(initial == Some(state.id()));
+            lines.push(match state_based {
+                true => {
+                    let marks = state.edges().first().map(marks).unwrap_or_default();
+                    format!("{} {flag} {marks}-1", state.id())
+                }
+                false => format!("{} {flag}", state.id()),
+            });
+            for edge in state.edges() {
+                let target = edge.state_conjunction().0[0];
+                let marks = match state_based {
+                    true => String::new(),
+                    false => format!("{}-1 ", marks(edge)),
+                };
+                lines.push(format!("{target} {marks}{}", guard(&edge.label().0, aps)));
+            }
+            lines.push("-1".to_string());
+        }
+        writeln!(self.out, "{}", lines.join("\n"))
+    }
+}
+
+/// Collects the sets of a condition that is a conjunction of `Inf` atoms in the order of
+/// their occurrence. Returns false if the condition has a different shape.
+fn generalized_buchi_sets(condition: &AcceptanceCondition, sets: &mut Vec<Id>) -> bool {
+    match condition {
+        AcceptanceCondition::Inf(AcceptanceAtom::Positive(set)) => {
+            if !sets.contains(set) {
+                sets.push(*set);
+            }
+            true
+        }
+        AcceptanceCondition::And(left, right) => {
+            generalized_buchi_sets(left, sets) && generalized_buchi_sets(right, sets)
+        }
+        AcceptanceCondition::Boolean(value) => value.0,
+        _ => false,
+    }
+}
+
+/// Writes a label expression in prefix notation, atomic propositions are named `p` followed
+/// by their index unless they have a name in `aps`.
+fn guard(expression: &AbstractLabelExpression, aps: &[String]) -> String {
+    let operands =
+        |operator: &str, operands: &[AbstractLabelExpression], neutral: &str| match operands
+            .split_last()
+        {
+            None => neutral.to_string(),
+            Some((last, rest)) => rest
+                .iter()
+                .map(|operand| format!("{operator} {} ", guard(operand, aps)))
+                .chain([guard(last, aps)])
+                .join(""),
+        };
+    match expression {
+        AbstractLabelExpression::Boolean(true) => "t".to_string(),
+        AbstractLabelExpression::Boolean(false) => "f".to_string(),
+        AbstractLabelExpression::Integer(ap) => match aps.get(*ap as usize) {
+            Some(name) if is_numbered(name) => name.clone(),
+            Some(name) => format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\"")),
+            None => format!("p{ap}"),
+        },
+        AbstractLabelExpression::Negated(operand) => format!("! {}", guard(operand, aps)),
+        AbstractLabelExpression::Conjunction(conjuncts) => operands("&", conjuncts, "t"),
+        AbstractLabelExpression::Disjunction(disjuncts) => operands("|", disjuncts, "f"),
+    }
+}
+
+/// Returns true if the name has the form `p` followed by digits, which is how atomic
+/// propositions are named in LBTT without quotes.
+fn is_numbered(name: &str) -> bool {
+    name.strip_prefix('p')
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Splits the input into whitespace separated tokens, quoted strings are single tokens.
+struct Tokens<'a> {
+    input: &'a str,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, line: 1 }
+    }
+
+    /// Returns the next token and the line it starts in. Fails on a quoted string without
+    /// a closing quote.
+    fn next(&mut self) -> Result<Option<(&'a str, usize)>, String> {
+        let trimmed = self.input.trim_start();
+        self.line += self.input[..self.input.len() - trimmed.len()]
+            .matches('\n')
+            .count();
+        let end = match trimmed.strip_prefix('"') {
+            Some(rest) => {
+                let mut escaped = false;
+                rest.char_indices()
+                    .find(|(_, c)| {
+                        let end = !escaped && *c == '"';
+                        escaped = !escaped && *c == '\\';
+                        end
+                    })
+                    .map(|(i, _)| i + 2)
+                    .ok_or_else(|| format!("line {}: Unterminated string", self.line))?
+            }
+            None => trimmed.find(char::is_whitespace).unwrap_or(trimmed.len()),
+        };
+        let (token, rest) = trimmed.split_at(end);
+        self.input = rest;
+        self.line += token.matches('\n').count();
+        Ok((!token.is_empty()).then_some((token, self.line)))
+    }
+
+    /// Reads an integer, `description` says what is expected in the error message.
+    fn number(&mut self, description: &str) -> Result<i64, String> {
+        match self.next()? {
+            Some((token, line)) => token
+                .parse()
+                .map_err(|_| format!("line {line}: Expected {description}, found {token:?}")),
+            None => Err(format!(
+                "Expected {description}, found the end of the input"
+            )),
+        }
+    }
+
+    /// Reads acceptance sets up to the terminating `-1`.
+    fn marks(&mut self, sets: Id) -> Result<Vec<Id>, String> {
+        let mut marks = vec![];
+        loop {
+            match self.number("an acceptance set or -1")? {
+                -1 => return Ok(marks),
+                set if (0..sets as i64).contains(&set) => marks.push(set as Id),
+                set => {
+                    return Err(format!(
+                        "line {}: Acceptance set {set} is out of range, there are {sets} sets",
+                        self.line
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Reads a guard in prefix notation, registering atomic propositions in `aps`. Returns
+    /// the guard together with its number of nodes, which is at most [`MAX_GUARD_SIZE`].
+    fn guard(
+        &mut self,
+        aps: &mut Vec<String>,
+        depth: usize,
+    ) -> Result<(AbstractLabelExpression, usize), String> {
+        if depth > MAX_NESTING {
+            return Err(format!(
+                "line {}: The guard is nested deeper than {MAX_NESTING} levels",
+                self.line
+            ));
+        }
+        let Some((token, line)) = self.next()? else {
+            return Err("Expected a guard, found the end of the input".to_string());
+        };
+        let mut operand = || self.guard(aps, depth + 1);
+        let (expression, size) = match token {
+            "t" => (AbstractLabelExpression::Boolean(true), 1),
+            "f" => (AbstractLabelExpression::Boolean(false), 1),
+            "!" => {
+                let (operand, size) = operand()?;
+                (
+                    AbstractLabelExpression::Negated(Box::new(operand)),
+                    size + 1,
+                )
+            }
+            "&" | "|" | "i" | "e" | "^" => {
+                let ((left, left_size), (right, right_size)) = (operand()?, operand()?);
+                // `e` and `^` repeat their operands
+                let size = match token {
+                    "&" | "|" => 1 + left_size + right_size,
+                    "i" => 2 + left_size + right_size,
+                    _ => 5 + 2 * (left_size + right_size),
+                };
+                if size > MAX_GUARD_SIZE {
+                    return Err(format!(
+                        "line {line}: The guard has more than {MAX_GUARD_SIZE} nodes"
+                    ));
+                }
+                let negated = |e: &AbstractLabelExpression| {
+                    AbstractLabelExpression::Negated(Box::new(e.clone()))
+                };
+                use AbstractLabelExpression::{Conjunction, Disjunction};
+                let expression = match token {
+                    "&" => Conjunction(vec![left, right]),
+                    "|" => Disjunction(vec![left, right]),
+                    "i" => Disjunction(vec![negated(&left), right]),
+                    "e" => Disjunction(vec![
+                        Conjunction(vec![left.clone(), right.clone()]),
+                        Conjunction(vec![negated(&left), negated(&right)]),
+                    ]),
+                    _ => Disjunction(vec![
+                        Conjunction(vec![left.clone(), negated(&right)]),
+                        Conjunction(vec![negated(&left), right]),
+                    ]),
+                };
+                (expression, size)
+            }
+            _ => {
+                let name = match token.strip_prefix('"') {
+                    // the tokens only contain terminated strings
+                    Some(quoted) => quoted[..quoted.len() - 1]
+                        .replace("\\\"", "\"")
+                        .replace("\\\\", "\\"),
+                    None if is_numbered(token) => token.to_string(),
+                    None => return Err(format!("line {line}: Unexpected {token:?} in a guard")),
+                };
+                let ap = match aps.iter().position(|ap| *ap == name) {
+                    Some(ap) => ap,
+                    None => {
+                        aps.push(name);
+                        aps.len() - 1
+                    }
+                };
+                (AbstractLabelExpression::Integer(ap as Id), 1)
+            }
+        };
+        Ok((expression, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_lbtt, to_lbtt, LbttWriter, MAX_GUARD_SIZE};
+    use crate::{input::MAX_NESTING, output::AcceptancePlacement, HoaAutomaton};
+
+    const STATE_BASED: &str = r#"2 1
+0 1 -1
+1 & p0 ! "b c"
+0 t
+-1
+1 0 0 -1
+1 i p0 "b c"
+-1
+"#;
+
+    #[test]
+    fn read_state_based() {
+        let aut = from_lbtt(STATE_BASED).unwrap();
+        let expected = HoaAutomaton::try_from(
+            r#"HOA: v1
+States: 2
+Start: 0
+AP: 2 "p0" "b c"
+acc-name: Buchi
+Acceptance: 1 Inf(0)
+properties: trans-labels explicit-labels state-acc
+--BODY--
+State: 0
+[0 & !1] 1
+[t] 0
+State: 1
+[!0 | 1] 1 {0}
+--END--"#,
+        )
+        .unwrap();
+        assert_eq!(aut, expected);
+
+        let mut writer = LbttWriter::new(vec![]).acceptance(AcceptancePlacement::State);
+        writer.write(&aut).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            written,
+            "2 1\n0 1 -1\n1 & p0 ! \"b c\"\n0 t\n-1\n1 0 0 -1\n1 | ! p0 \"b c\"\n-1\n"
+        );
+        assert_eq!(from_lbtt(&written).unwrap(), aut);
+    }
+
+    #[test]
+    fn transition_based_round_trip() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+States: 3
+Start: 2
+AP: 2 "a" "p1"
+Acceptance: 3 Inf(2) & Inf(0)
+--BODY--
+State: 0
+[0 & 1 & !0] 1 {0 1 2}
+State: 1
+[f] 2
+State: 2
+[0 | 1] 0 {2}
+--END--"#,
+        )
+        .unwrap();
+        let written = to_lbtt(&aut).unwrap();
+        assert_eq!(
+            written,
+            "3 2t\n0 0\n1 0 1 -1 & \"a\" & p1 ! \"a\"\n-1\n1 0\n2 -1 f\n-1\n2 1\n0 0 -1 | \"a\" p1\n-1\n"
+        );
+        let read = from_lbtt(&written).unwrap();
+        assert_eq!(read.aps(), ["a", "p1"]);
+        assert_eq!(read.start().len(), 1);
+        assert_eq!(read.body()[0].edges()[0].acceptance_signature().0, [0, 1]);
+        assert_eq!(to_lbtt(&read).unwrap(), written);
+    }
+
+    #[test]
+    fn unsupported_and_malformed() {
+        let parity = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 1\nStart: 0\nAcceptance: 2 Inf(0) | Fin(1)\n--BODY--\nState: 0\n[t] 0\n--END--",
+        )
+        .unwrap();
+        assert!(to_lbtt(&parity).is_err());
+        let universal = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 2\nStart: 0 & 1\nAcceptance: 0 t\n--BODY--\nState: 0\nState: 1\n--END--",
+        )
+        .unwrap();
+        assert!(to_lbtt(&universal).is_err());
+
+        assert_eq!(
+            from_lbtt("1 1\n0 1 -1\n3 t\n-1").unwrap_err(),
+            "A transition leads to state 3, which is not listed"
+        );
+        assert_eq!(
+            from_lbtt("1 1\n0 1 -1\n0 & a\n-1").unwrap_err(),
+            "line 3: Unexpected \"a\" in a guard"
+        );
+        assert!(from_lbtt("1 1\n0 1 2 -1\n0 t\n-1").is_err());
+        assert!(from_lbtt("1 1t\n0 1\n0 -1 t\n").is_err());
+        assert!(from_lbtt("-1 0").is_err());
+        assert!(from_lbtt("0 4294967295").is_err());
+        assert_eq!(
+            from_lbtt("1 0\n0 1 -1\n0 \"abc\\\"\n-1").unwrap_err(),
+            "line 3: Unterminated string"
+        );
+        let deep = format!("1 0\n0 1 -1\n0 {}t\n-1", "! ".repeat(100_000));
+        assert!(from_lbtt(&deep).is_err());
+        // every `e` repeats its operands, deep nesting is rejected instead of exhausting memory
+        let equivalent = |depth: usize| {
+            let guard = format!("{}p0{}", "e ".repeat(depth), " p1".repeat(depth));
+            from_lbtt(&format!("1 0\n0 1 -1\n0 {guard}\n-1"))
+        };
+        let shallow = equivalent(2).unwrap();
+        assert_eq!(
+            shallow.body()[0].edges()[0].label().to_string(),
+            "[(0 & 1 | !0 & !1) & 1 | !(0 & 1 | !0 & !1) & !1]"
+        );
+        assert_eq!(
+            equivalent(MAX_NESTING).unwrap_err(),
+            format!("line 3: The guard has more than {MAX_GUARD_SIZE} nodes")
+        );
+
+        // the conjunction of many sets is balanced, so it can be written and read as HOA
+        let many = from_lbtt("1 1000\n0 1 -1\n0 t\n-1").unwrap();
+        let hoa = crate::output::to_hoa(&many);
+        assert_eq!(HoaAutomaton::try_from(hoa.as_str()), Ok(many));
+    }
+}
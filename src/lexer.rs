@@ -42,8 +42,19 @@ impl std::fmt::Display for Token {
 pub fn tokenizer() -> impl Parser<char, Vec<(Token, Span)>, Error = Simple<char>> {
     let int = text::int(10).map(Token::Int);
 
+    // `\"` and `\\` stand for a quote and a backslash, any other escape is kept as is.
+    let escape = just('\\').ignore_then(any()).map(|c| match c {
+        '"' | '\\' => vec![c],
+        _ => vec!['\\', c],
+    });
     let str_ = just('"')
-        .ignore_then(filter(|c| *c != '"').repeated())
+        .ignore_then(
+            filter(|c| *c != '"' && *c != '\\')
+                .map(|c| vec![c])
+                .or(escape)
+                .repeated()
+                .flatten(),
+        )
         .then_ignore(just('"'))
         .collect::<String>()
         .map(Token::Text);
@@ -53,9 +64,22 @@ pub fn tokenizer() -> impl Parser<char, Vec<(Token, Span)>, Error = Simple<char>
     let paren = one_of(r#"(){}[]"#).map(Token::Paren);
 
     let raw_ident = filter(|c: &char| c.is_ascii_alphabetic() || *c == '_')
-        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || *c == '-').repeated())
+        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')).repeated())
         .collect::<String>();
 
+    // dots are not allowed in identifiers, but they appear in version strings like `v1.1`
+    let version = just('v')
+        .chain(text::digits(10))
+        .chain::<char, _, _>(
+            just('.')
+                .chain(text::digits(10))
+                .repeated()
+                .at_least(1)
+                .flatten(),
+        )
+        .collect::<String>()
+        .map(Token::Identifier);
+
     let ident = raw_ident.map(|ident: String| match ident.as_str() {
         "Fin" => Token::Fin,
         "Inf" => Token::Inf,
@@ -77,6 +101,7 @@ pub fn tokenizer() -> impl Parser<char, Vec<(Token, Span)>, Error = Simple<char>
         .or(end)
         .or(body)
         .or(header)
+        .or(version)
         .or(str_)
         .or(op)
         .or(paren)
@@ -1,11 +1,34 @@
 //! This crate provides a parser for the HOA format.
 // #![warn(missing_docs)]
+mod alphabet;
 mod body;
+mod builder;
+pub mod dot;
+pub mod dstar;
+mod edit;
+mod error;
+mod fast;
 mod format;
+#[cfg(test)]
+mod fuzz;
+#[cfg(feature = "goal")]
+pub mod goal;
 mod header;
 pub mod input;
+pub mod language;
+pub mod lbtt;
 mod lexer;
+pub mod minimisation;
 pub mod output;
+pub mod promela;
+#[cfg(any(test, feature = "random"))]
+pub mod random;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod simulation;
+mod span;
+pub mod subset;
+mod symbolic;
 mod value;
 
 use biodivine_lib_bdd::{Bdd, BddPartialValuation, BddVariable, BddVariableSet};
@@ -14,15 +37,16 @@ use std::fmt::{Debug, Display};
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum AbstractLabelExpression {
     Boolean(bool),
-    Integer(u16),
+    Integer(Id),
     Negated(Box<AbstractLabelExpression>),
     Conjunction(Vec<AbstractLabelExpression>),
     Disjunction(Vec<AbstractLabelExpression>),
 }
 
+#[derive(Clone, Copy)]
 pub(crate) enum Atomic {
-    Positive(u16),
-    Negative(u16),
+    Positive(Id),
+    Negative(Id),
 }
 
 impl Atomic {
@@ -52,7 +76,7 @@ impl AbstractLabelExpression {
                 false => vs.mk_false(),
             }),
             AbstractLabelExpression::Integer(i) => {
-                if i < vs.num_vars() {
+                if (i as usize) < vars.len() {
                     Ok(vs.mk_var(vars[i as usize]))
                 } else {
                     Err(format!("AP identifier {i} is too high"))
@@ -66,9 +90,9 @@ impl AbstractLabelExpression {
                     );
                     Ok(vs.mk_conjunctive_clause(&valuation))
                 } else {
-                    Err(format!(
-                        "could not parse label expression conjunct {cs:?}, expected atom"
-                    ))
+                    cs.into_iter().try_fold(vs.mk_true(), |acc, c| {
+                        Ok(acc.and(&c.try_into_bdd(vs, vars)?))
+                    })
                 }
             }
             AbstractLabelExpression::Disjunction(ds) => {
@@ -78,46 +102,79 @@ impl AbstractLabelExpression {
                     );
                     Ok(vs.mk_disjunctive_clause(&valuation))
                 } else {
-                    Err(format!(
-                        "could not parse label expression disjunct {ds:?}, expected atom"
-                    ))
+                    ds.into_iter().try_fold(vs.mk_false(), |acc, d| {
+                        Ok(acc.or(&d.try_into_bdd(vs, vars)?))
+                    })
                 }
             }
         }
     }
-}
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
-pub enum LabelExpression {
-    Parsed(Bdd),
-    Abstract(AbstractLabelExpression),
+    /// Converts a [`Bdd`] back into a label expression in disjunctive normal form. The
+    /// position of a variable in `vars` determines the AP index it is mapped to.
+    pub fn from_bdd(bdd: &Bdd, vars: &[BddVariable]) -> Self {
+        let literal = |var: BddVariable, value: bool| {
+            let ap = AbstractLabelExpression::Integer(
+                vars.iter()
+                    .position(|v| *v == var)
+                    .expect("unknown BDD variable") as Id,
+            );
+            if value {
+                ap
+            } else {
+                AbstractLabelExpression::Negated(Box::new(ap))
+            }
+        };
+        let mut clauses = bdd
+            .to_optimized_dnf()
+            .into_iter()
+            .map(|clause| {
+                let mut literals = clause
+                    .to_values()
+                    .into_iter()
+                    .map(|(var, value)| literal(var, value))
+                    .collect_vec();
+                match literals.len() {
+                    0 => AbstractLabelExpression::Boolean(true),
+                    1 => literals.pop().unwrap(),
+                    _ => AbstractLabelExpression::Conjunction(literals),
+                }
+            })
+            .collect_vec();
+        match clauses.len() {
+            0 => AbstractLabelExpression::Boolean(false),
+            1 => clauses.pop().unwrap(),
+            _ => AbstractLabelExpression::Disjunction(clauses),
+        }
+    }
 }
 
-pub const MAX_APS: usize = 8;
-
-pub fn build_vars(count: u16) -> (BddVariableSet, Vec<BddVariable>) {
+/// Creates a set of `count` anonymous BDD variables, one for each atomic proposition of an
+/// automaton, see also [`Alphabet`]. Fails if `count` exceeds [`u16::MAX`], the largest
+/// number of variables a [`BddVariableSet`] can hold.
+pub fn build_vars(count: usize) -> Result<(BddVariableSet, Vec<BddVariable>), String> {
+    let count = u16::try_from(count).map_err(|_| {
+        format!(
+            "{count} atomic propositions exceed the {} that BDDs support",
+            u16::MAX
+        )
+    })?;
     let vs = BddVariableSet::new_anonymous(count);
-    let vars = vs.variables().into_iter().take(count as usize).collect();
-    (vs, vars)
+    let vars = vs.variables();
+    Ok((vs, vars))
 }
 
+/// Returns the position right after the first automaton in `input`, which ends with
+/// `--END--` or `--ABORT--`.
 pub fn first_automaton_split_position(input: &str) -> Option<usize> {
-    const ENDLEN: usize = "--END--".len();
-
-    'outer: loop {
-        if let Some(end) = input.find("--END--") {
-            if let Some(abort) = input.find("--ABORT--") {
-                if abort < end {
-                    continue 'outer;
-                }
-            }
-            return Some(end + ENDLEN);
-        } else {
-            return None;
-        }
-    }
+    ["--END--", "--ABORT--"]
+        .into_iter()
+        .filter_map(|marker| input.find(marker).map(|start| start + marker.len()))
+        .min()
 }
 
+/// Parses every automaton in a stream of HOA automata, automata that do not parse are
+/// skipped.
 pub fn parse_hoa_automata(input: &str) -> Vec<HoaAutomaton> {
     let mut out = Vec::new();
     for hoa_aut in input.split_inclusive("--END--") {
@@ -126,14 +183,12 @@ pub fn parse_hoa_automata(input: &str) -> Vec<HoaAutomaton> {
         }
         match hoa_aut.try_into() {
             Ok(aut) => out.push(aut),
-            Err(e) => println!("Error when parsing automaton: {}", e),
+            Err(e) => tracing::debug!("Skipping automaton that does not parse: {}", e),
         }
     }
     out
 }
 
-use ariadne::{Color, Fmt, ReportKind, Source};
-
 #[allow(unused_imports)]
 use chumsky::prelude::*;
 pub use format::*;
@@ -143,8 +198,13 @@ pub use format::{
     AcceptanceCondition, AcceptanceInfo, AcceptanceName, AcceptanceSignature, AliasName, Property,
 };
 
+pub use alphabet::Alphabet;
 pub use body::{Body, Edge, Label, State};
+pub use builder::AutomatonBuilder;
+pub use error::{ParseError, ParseErrorKind};
 pub use header::{Header, HeaderItem};
+pub use lexer::Span;
+pub use span::Spans;
 
 use itertools::Itertools;
 use lexer::Token;
@@ -155,7 +215,7 @@ pub type Id = u32;
 /// Represents the different types of error that can be encountered when parsing a [`HoaAutomaton`].
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum FromHoaError {
-    /// The version string does not match, we only support v1.
+    /// The version string does not match, we only support v1 and v1.1.
     UnsupportedVersion(String),
     /// Encapsulates that an unsupported acceptance condition was used.
     UnsupportedAcceptanceCondition,
@@ -163,10 +223,10 @@ pub enum FromHoaError {
     ParseAcceptanceCondition(String),
     /// There was an error in the body.
     UnsupportedBody,
-    /// Lexer encountered an error, contains detailed report.
-    LexerError(String),
-    /// Parser encountered an error, contains detailed report.
-    ParserError(String),
+    /// Lexer encountered errors, contains their structured descriptions.
+    LexerError(Vec<ParseError>),
+    /// Parser encountered errors, contains their structured descriptions.
+    ParserError(Vec<ParseError>),
     /// Abort token was encountered.
     Abort,
 }
@@ -185,8 +245,35 @@ impl Display for FromHoaError {
                 write!(f, "Could not parse acceptance condition: {}", message)
             }
             FromHoaError::Abort => write!(f, "Abort token encountered"),
-            FromHoaError::LexerError(rep) => write!(f, "Lexer error: {}", rep),
-            FromHoaError::ParserError(rep) => write!(f, "Parser error: {}", rep),
+            FromHoaError::LexerError(errors) => {
+                write!(f, "Lexer error: {}", errors.iter().join("; "))
+            }
+            FromHoaError::ParserError(errors) => {
+                write!(f, "Parser error: {}", errors.iter().join("; "))
+            }
+        }
+    }
+}
+
+impl FromHoaError {
+    /// Returns the structured lexer or parser errors, which is empty for other kinds of error.
+    pub fn errors(&self) -> &[ParseError] {
+        match self {
+            FromHoaError::LexerError(errors) | FromHoaError::ParserError(errors) => errors,
+            _ => &[],
+        }
+    }
+
+    /// Renders lexer and parser errors as [`ariadne`] reports that point into `input`, with
+    /// ANSI colours if `colored` is set. Other errors are rendered as their message.
+    #[cfg(feature = "ariadne")]
+    pub fn render(&self, input: &str, colored: bool) -> String {
+        match self.errors() {
+            [] => self.to_string(),
+            errors => errors
+                .iter()
+                .map(|error| error.render(input, colored))
+                .join("\n"),
         }
     }
 }
@@ -195,23 +282,39 @@ impl Display for FromHoaError {
 /// a [`Header`] and a [`Body`].
 /// The header contains all the information about the automaton (e.g. the number of states, the
 /// acceptance condition, aliases etc.) and the body contains the actual transitions.
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Automata that were parsed additionally carry the [`Spans`] of their elements, which are
+/// ignored when comparing automata.
+/// With the `serde` feature, automata can be serialized, the JSON schema is described in the
+/// README.
+#[derive(Debug, Clone)]
 pub struct HoaAutomaton {
     header: Header,
     body: Body,
+    spans: Option<Spans>,
 }
 
+impl PartialEq for HoaAutomaton {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header && self.body == other.body
+    }
+}
+
+impl Eq for HoaAutomaton {}
+
 /// Represents an acceptance condition as it is encoded in a HOA automaton.
 pub type HoaAcceptance = (usize, AcceptanceCondition);
 
 /// Stores information on aliases, it holds a vector of pairs of alias
 /// names and label expression. This can be used to unalias an automaton.
-pub type Aliases = Vec<(AliasName, LabelExpression)>;
+pub type Aliases = Vec<(AliasName, AbstractLabelExpression)>;
 
 impl HoaAutomaton {
-    /// Adds the given state.
+    /// Adds the given state, keeping the states sorted by id and the `States:` header
+    /// up to date.
     pub fn add_state(&mut self, state: State) {
-        self.body.push(state);
+        let position = self.body.partition_point(|other| other.id() <= state.id());
+        self.body.insert(position, state);
+        self.update_states();
     }
 
     /// Returns the version of the HOA file.
@@ -237,14 +340,52 @@ impl HoaAutomaton {
         &mut self.body
     }
 
-    fn from_parsed((header, body): (Header, Body)) -> Self {
-        Self::from_parts(header, body)
+    fn from_parsed(
+        ((header, header_spans), (body, (state_spans, edge_spans))): (
+            (Header, Vec<Span>),
+            (Body, body::BodySpans),
+        ),
+    ) -> Self {
+        // sort the spans in the same way as `from_parts` sorts the states
+        let order = (0..body.len())
+            .sorted_by_key(|i| body[*i].id())
+            .collect_vec();
+        let spans = Spans {
+            header: header_spans,
+            states: order.iter().map(|i| state_spans[*i].clone()).collect(),
+            edges: order.iter().map(|i| edge_spans[*i].clone()).collect(),
+        };
+        let mut out = Self::from_parts(header, body);
+        out.spans = Some(spans);
+        out
     }
 
     /// Parses a HOA automaton from a string.
     pub fn parser() -> impl Parser<Token, Self, Error = Simple<Token>> {
-        Header::parser()
-            .then(Body::parser())
+        // quotes up to 40 characters of the trailing input, which may be a whole automaton
+        let trailing = any().repeated().validate(|tokens: Vec<Token>, span, emit| {
+            if !tokens.is_empty() {
+                let text = tokens.iter().join(" ");
+                let quoted = match text.char_indices().nth(40) {
+                    Some((end, _)) => format!("{}…", &text[..end]),
+                    None => text,
+                };
+                emit(error::quirk(
+                    span,
+                    format!("Unexpected trailing input after --END--: \"{quoted}\""),
+                ))
+            }
+        });
+        Header::spanned_parser()
+            .then_with(|(header, header_spans)| {
+                let aps = header.iter().find_map(|item| match item {
+                    HeaderItem::AP(aps) => Some(aps.len()),
+                    _ => None,
+                });
+                Body::spanned_parser(aps.unwrap_or(0))
+                    .map(move |body| ((header.clone(), header_spans.clone()), body))
+            })
+            .then_ignore(trailing)
             .then_ignore(end())
             .map(HoaAutomaton::from_parsed)
     }
@@ -252,24 +393,35 @@ impl HoaAutomaton {
     /// Creates a new HOA automaton from the given version, header and
     /// body. This function will also unalias the automaton.
     pub fn from_parts(header: Header, body: Body) -> Self {
-        let mut out = Self { header, body };
-        out.body.sort_by(|x, y| x.0.cmp(&y.0));
+        let mut out = Self {
+            header,
+            body,
+            spans: None,
+        };
+        out.body.sort_by_key(|x| x.0);
         out
     }
 
+    /// Returns the source spans of the header items, states and edges if the automaton
+    /// was parsed.
+    pub fn spans(&self) -> Option<&Spans> {
+        self.spans.as_ref()
+    }
+
     /// Verifies that the automaton is well-formed. This means that
     /// - the number of states is set correctly
     /// - all states are defined exactly once
+    /// - all controllable atomic propositions are declared
     pub fn verify(&self) -> Result<(), String> {
         let mut errors = Vec::new();
-        let mut states = Vec::new();
-        for state in self.body().iter() {
-            if states.contains(&state.id()) {
-                errors.push(format!("State {} is defined more than once!", state.id()));
+        let mut states = self.body().iter().map(State::id).collect_vec();
+        states.sort_unstable();
+        for (left, right) in states.iter().tuple_windows() {
+            if left == right {
+                errors.push(format!("State {} is defined more than once!", left));
             }
-            states.push(state.id());
         }
-        if let Some(num_states) = self.num_states() {
+        if let Some(num_states) = self.header().count_states() {
             if states.len() != num_states {
                 errors.push(format!(
                     "The number of states is set to {} but there are {} states!",
@@ -278,6 +430,18 @@ impl HoaAutomaton {
                 ));
             }
         }
+        let num_aps = self.header().iter().find_map(|item| match item {
+            HeaderItem::AP(aps) => Some(aps.len()),
+            _ => None,
+        });
+        for ap in self.controllable_aps() {
+            if num_aps.is_none_or(|num_aps| ap as usize >= num_aps) {
+                errors.push(format!(
+                    "The controllable atomic proposition {} is not declared!",
+                    ap
+                ));
+            }
+        }
         if errors.is_empty() {
             Ok(())
         } else {
@@ -303,14 +467,6 @@ impl HoaAutomaton {
 
     /// Returns the number of edges in the automaton.
     pub fn start(&self) -> Vec<&StateConjunction> {
-        debug_assert!(
-            self.header()
-                .iter()
-                .filter(|item| matches!(item, HeaderItem::Start(_)))
-                .count()
-                >= 1,
-            "At least one initial state conjunction has to be present!"
-        );
         self.header()
             .iter()
             .filter_map(|item| match item {
@@ -320,18 +476,29 @@ impl HoaAutomaton {
             .collect()
     }
 
-    /// Returns the set of all atomic propositions in the automaton.
-    pub fn aps(&self) -> &Vec<String> {
-        let aps = self
-            .header()
+    /// Returns the set of all atomic propositions in the automaton, which is empty if there
+    /// is no `AP:` header.
+    pub fn aps(&self) -> &[String] {
+        self.header()
+            .iter()
+            .find_map(|item| match item {
+                HeaderItem::AP(ap) => Some(ap.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the indices of the atomic propositions that are controllable, which is empty
+    /// if there is no `controllable-AP:` header.
+    pub fn controllable_aps(&self) -> Vec<Id> {
+        self.header()
             .iter()
             .filter_map(|item| match item {
-                HeaderItem::AP(ap) => Some(ap),
+                HeaderItem::ControllableAP(aps) => Some(aps.iter().copied()),
                 _ => None,
             })
-            .collect_vec();
-        debug_assert!(aps.len() == 1, "There must be exactly one AP header!");
-        aps.first().unwrap()
+            .flatten()
+            .collect()
     }
 
     /// Counts the number of atomic propositions in the automaton.
@@ -378,8 +545,8 @@ impl HoaAutomaton {
                 .iter()
                 .filter(|item| matches!(item, HeaderItem::AcceptanceName(..)))
                 .count()
-                == 1,
-            "There must be exactly one AcceptanceName header!"
+                <= 1,
+            "There must be at most one AcceptanceName header!"
         );
         self.header().iter().find_map(|item| match item {
             HeaderItem::AcceptanceName(name, info) => Some((name, info)),
@@ -407,125 +574,127 @@ impl TryFrom<&str> for HoaAutomaton {
     }
 }
 
-impl std::fmt::Display for AbstractLabelExpression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl AbstractLabelExpression {
+    /// Displays the expression with the atomic propositions replaced by their names in `aps`,
+    /// as in `a & !b`. Names that are not identifiers are quoted and propositions without a
+    /// name are written as their index.
+    pub fn with_names<'a>(&'a self, aps: &'a [String]) -> impl Display + 'a {
+        NamedLabel(self, aps)
+    }
+
+    /// Writes the expression, naming the atomic propositions that occur in `aps`.
+    fn fmt_with(&self, aps: &[String], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             AbstractLabelExpression::Boolean(b) => match b {
                 true => write!(f, "t"),
                 false => write!(f, "f"),
             },
-            AbstractLabelExpression::Integer(i) => write!(f, "{i}"),
+            AbstractLabelExpression::Integer(i) => match aps.get(*i as usize) {
+                Some(name) if is_identifier(name) => write!(f, "{name}"),
+                Some(name) => write!(f, "{}", output::quoted(name)),
+                None => write!(f, "{i}"),
+            },
             AbstractLabelExpression::Negated(expr) => {
-                write!(f, "!{}", expr)
+                write!(f, "!")?;
+                expr.fmt_nested(aps, f)
             }
-            AbstractLabelExpression::Conjunction(conjuncts) => {
-                let mut it = conjuncts.iter();
-                if let Some(first) = it.next() {
-                    Display::fmt(first, f)?;
-                }
-                for succ in it {
-                    write!(f, " & ")?;
-                    Display::fmt(succ, f)?;
-                }
-                Ok(())
+            AbstractLabelExpression::Conjunction(conjuncts) => match conjuncts.as_slice() {
+                [] => write!(f, "t"),
+                [single] => single.fmt_with(aps, f),
+                _ => Self::fmt_operands(conjuncts, "&", aps, f),
+            },
+            AbstractLabelExpression::Disjunction(disjuncts) => match disjuncts.as_slice() {
+                [] => write!(f, "f"),
+                [single] => single.fmt_with(aps, f),
+                _ => Self::fmt_operands(disjuncts, "|", aps, f),
+            },
+        }
+    }
+
+    /// Writes the expression, wrapped in parentheses unless it is a constant or an atomic
+    /// proposition, so that it parses back into the same expression when nested.
+    fn fmt_nested(&self, aps: &[String], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbstractLabelExpression::Boolean(_) | AbstractLabelExpression::Integer(_) => {
+                self.fmt_with(aps, f)
             }
-            AbstractLabelExpression::Disjunction(disjuncts) => {
-                let mut it = disjuncts.iter();
-                if let Some(first) = it.next() {
-                    Display::fmt(first, f)?;
-                }
-                for succ in it {
-                    write!(f, " | ")?;
-                    Display::fmt(succ, f)?;
+            _ => {
+                write!(f, "(")?;
+                self.fmt_with(aps, f)?;
+                write!(f, ")")
+            }
+        }
+    }
+
+    /// Writes the operands separated by the given operator, nested compound expressions
+    /// are parenthesized unless they bind more strongly than the operator.
+    fn fmt_operands(
+        operands: &[AbstractLabelExpression],
+        operator: &str,
+        aps: &[String],
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        for (i, operand) in operands.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {operator} ")?;
+            }
+            match operand {
+                AbstractLabelExpression::Negated(_) => operand.fmt_with(aps, f)?,
+                AbstractLabelExpression::Conjunction(_) if operator == "|" => {
+                    operand.fmt_with(aps, f)?
                 }
-                Ok(())
+                _ => operand.fmt_nested(aps, f)?,
             }
         }
+        Ok(())
     }
 }
 
-fn build_error_report<I: Iterator<Item = Simple<String>>>(input: &str, errs: I) -> String {
-    errs.into_iter()
-        .map(|e| {
-            let report = ariadne::Report::build(ReportKind::Error, (), e.span().start);
-
-            let report = match e.reason() {
-                chumsky::error::SimpleReason::Unexpected => report
-                    .with_message(format!(
-                        "{}, expected {}",
-                        if e.found().is_some() {
-                            "Unexpected token in input"
-                        } else {
-                            "Unexpected end of input"
-                        },
-                        if e.expected().len() == 0 {
-                            "something else".to_string()
-                        } else {
-                            e.expected()
-                                .map(|expected| match expected {
-                                    Some(expected) => expected.to_string(),
-                                    None => "end of input".to_string(),
-                                })
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        }
-                    ))
-                    .with_label(
-                        ariadne::Label::new(e.span())
-                            .with_message(format!(
-                                "Unexpected token {}",
-                                e.found()
-                                    .unwrap_or(&"end of file".to_string())
-                                    .fg(Color::Red)
-                            ))
-                            .with_color(Color::Red),
-                    ),
-                chumsky::error::SimpleReason::Unclosed { span, delimiter } => report
-                    .with_message(format!(
-                        "Unclosed delimiter {}",
-                        delimiter.fg(Color::Yellow)
-                    ))
-                    .with_label(
-                        ariadne::Label::new(span.clone())
-                            .with_message(format!(
-                                "Unclosed delimiter {}",
-                                delimiter.fg(Color::Yellow)
-                            ))
-                            .with_color(Color::Yellow),
-                    )
-                    .with_label(
-                        ariadne::Label::new(e.span())
-                            .with_message(format!(
-                                "Must be closed before this {}",
-                                e.found()
-                                    .unwrap_or(&"end of file".to_string())
-                                    .fg(Color::Red)
-                            ))
-                            .with_color(Color::Red),
-                    ),
-                chumsky::error::SimpleReason::Custom(msg) => report.with_message(msg).with_label(
-                    ariadne::Label::new(e.span())
-                        .with_message(format!("{}", msg.fg(Color::Red)))
-                        .with_color(Color::Red),
-                ),
-            };
+/// Returns true if the name of an atomic proposition can be written without quotes, which
+/// is the case for identifiers other than the constants `t` and `f`.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && name != "t"
+        && name != "f"
+}
 
-            let mut report_output = Vec::new();
-            report
-                .finish()
-                .write(Source::from(input), &mut report_output)
-                .unwrap();
+/// Displays a label expression over the names of the atomic propositions, see
+/// [`AbstractLabelExpression::with_names`].
+struct NamedLabel<'a>(&'a AbstractLabelExpression, &'a [String]);
 
-            std::str::from_utf8(&report_output)
-                .unwrap_or("Could not parse error report")
-                .to_string()
-        })
-        .join("\n")
+impl Display for NamedLabel<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_with(self.1, f)
+    }
+}
+
+/// Writes the expression in HOA syntax. A conjunction or disjunction with a single operand
+/// is written as that operand and an empty one as `t` or `f`, which is how it parses back.
+impl std::fmt::Display for AbstractLabelExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(&[], f)
+    }
+}
+
+impl std::str::FromStr for AbstractLabelExpression {
+    type Err = String;
+
+    /// Parses an expression in HOA syntax like `0 & !(1 | t)`, the inverse of [`Display`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        input::parse_label(s, |token| token)
+            .map_err(|errors| errors.into_iter().map(|error| error.to_string()).join("\n"))
+    }
 }
 
 #[cfg(test)]
 fn print_error_report<I: Iterator<Item = Simple<String>>>(input: &str, errs: I) {
-    eprintln!("{}", build_error_report(input, errs))
+    for error in error::parse_errors(input, errs) {
+        eprintln!("{}", error)
+    }
 }
 
 #[cfg(test)]
@@ -533,8 +702,8 @@ mod tests {
     use crate::{
         body::{Edge, State},
         header::Header,
-        AcceptanceAtom, AcceptanceCondition, AcceptanceName, AcceptanceSignature, Body, HeaderItem,
-        HoaAutomaton, Label, StateConjunction, ALPHABET, VARS,
+        AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, AcceptanceName,
+        AcceptanceSignature, Body, HeaderItem, HoaAutomaton, Label, StateConjunction,
     };
 
     #[test]
@@ -545,6 +714,20 @@ mod tests {
         assert_eq!(first, Some(15));
     }
 
+    #[test]
+    fn label_with_names() {
+        let label = AbstractLabelExpression::Conjunction(vec![
+            AbstractLabelExpression::Integer(0),
+            AbstractLabelExpression::Negated(Box::new(AbstractLabelExpression::Integer(1))),
+            AbstractLabelExpression::Integer(2),
+        ]);
+        let aps = ["a".to_string(), "x \"é\"\n\\".to_string()];
+        assert_eq!(
+            label.with_names(&aps).to_string(),
+            "a & !\"x \\\"é\\\"\n\\\\\" & 2"
+        );
+    }
+
     #[test]
     fn real_test_1() {
         let contents = r#"HOA: v1
@@ -585,12 +768,14 @@ mod tests {
             None,
             vec![
                 Edge::from_parts(
-                    Label(ALPHABET.mk_var(VARS[0])),
+                    Label(AbstractLabelExpression::Integer(0)),
                     StateConjunction(vec![1]),
                     AcceptanceSignature(vec![0]),
                 ),
                 Edge::from_parts(
-                    Label(ALPHABET.mk_var(VARS[0]).not()),
+                    Label(AbstractLabelExpression::Negated(Box::new(
+                        AbstractLabelExpression::Integer(0),
+                    ))),
                     StateConjunction(vec![2]),
                     AcceptanceSignature(vec![0]),
                 ),
@@ -601,12 +786,14 @@ mod tests {
             None,
             vec![
                 Edge::from_parts(
-                    Label(ALPHABET.mk_var(VARS[0])),
+                    Label(AbstractLabelExpression::Integer(0)),
                     StateConjunction(vec![1]),
                     AcceptanceSignature(vec![]),
                 ),
                 Edge::from_parts(
-                    Label(ALPHABET.mk_var(VARS[0]).not()),
+                    Label(AbstractLabelExpression::Negated(Box::new(
+                        AbstractLabelExpression::Integer(0),
+                    ))),
                     StateConjunction(vec![2]),
                     AcceptanceSignature(vec![]),
                 ),
@@ -617,12 +804,14 @@ mod tests {
             None,
             vec![
                 Edge::from_parts(
-                    Label(ALPHABET.mk_var(VARS[0])),
+                    Label(AbstractLabelExpression::Integer(0)),
                     StateConjunction(vec![1]),
                     AcceptanceSignature(vec![]),
                 ),
                 Edge::from_parts(
-                    Label(ALPHABET.mk_var(VARS[0]).not()),
+                    Label(AbstractLabelExpression::Negated(Box::new(
+                        AbstractLabelExpression::Integer(0),
+                    ))),
                     StateConjunction(vec![2]),
                     AcceptanceSignature(vec![]),
                 ),
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use biodivine_lib_bdd::Bdd;
+use itertools::Itertools;
+
+use crate::{
+    language::{accepting_lasso, sccs},
+    symbolic::{Symbolic, SymbolicEdge},
+    AcceptanceCondition, AcceptanceName, HoaAutomaton, Id, Property,
+};
+
+/// The guards under which a state moves to a class of states with some acceptance sets.
+type Signature = Vec<((usize, Vec<Id>), Bdd)>;
+
+/// Minimises a deterministic automaton by symbolic partition refinement, two states are
+/// separated as soon as they differ in the guard under which they move to some class of
+/// states with some acceptance sets.
+///
+/// If the automaton is weak, i.e. in every strongly connected component either all or no
+/// cycles are accepting, the result is a minimal equivalent deterministic Büchi automaton.
+/// This covers all safety automata. To this end, every state is assigned a canonical colour
+/// first, from which acceptance can be read off, and the refinement starts from the
+/// partition induced by these colours. For any other automaton, the result is the quotient
+/// with respect to the coarsest bisimulation that preserves acceptance sets, which accepts
+/// the same language but is not necessarily minimal.
+pub fn minimise(aut: &HoaAutomaton) -> Result<HoaAutomaton, String> {
+    let mut sym = Symbolic::from_automaton(aut)?;
+    if !sym.is_deterministic() {
+        return Err("Minimisation requires a deterministic automaton".to_string());
+    }
+    sym.remove_unreachable();
+
+    let out = match minimise_weak(&sym) {
+        Some(out) => out,
+        None => {
+            let classes = refine(&sym, vec![0; sym.num_states()], true);
+            let mut out = quotient(&sym, &classes, true);
+            out.acceptance_name = sym.acceptance_name.clone();
+            out
+        }
+    };
+    Ok(out.into_automaton(vec![
+        Property::TransLabels,
+        Property::ExplicitLabels,
+        Property::TransitionAcceptance,
+        Property::Deterministic,
+    ]))
+}
+
+/// Minimises a weak deterministic automaton, returns `None` if the automaton is not weak.
+fn minimise_weak(sym: &Symbolic) -> Option<Symbolic> {
+    // Missing transitions lead to a rejecting sink, which is removed again in the end.
+    let mut complete = sym.clone();
+    let missing = (0..complete.num_states())
+        .map(|q| {
+            complete.edges[q]
+                .iter()
+                .fold(complete.vs.mk_true(), |acc, edge| acc.and_not(&edge.guard))
+        })
+        .collect_vec();
+    let sink = if complete.start.is_empty() || missing.iter().any(|guard| !guard.is_false()) {
+        let sink = complete.add_state(None);
+        for (q, guard) in missing.into_iter().enumerate() {
+            if !guard.is_false() {
+                complete.edges[q].push(SymbolicEdge {
+                    guard,
+                    targets: vec![sink],
+                    marks: vec![],
+                });
+            }
+        }
+        complete.edges[sink as usize].push(SymbolicEdge {
+            guard: complete.vs.mk_true(),
+            targets: vec![sink],
+            marks: vec![],
+        });
+        Some(sink as usize)
+    } else {
+        None
+    };
+
+    let colours = colours(&complete, sink)?;
+    let classes = refine(&complete, colours.clone(), false);
+    let mut out = quotient(&complete, &classes, false);
+    for (q, edges) in out.edges.iter_mut().enumerate() {
+        let colour = classes
+            .iter()
+            .position(|class| *class == q)
+            .map(|representative| colours[representative])
+            .expect("every class has a representative");
+        for edge in edges {
+            edge.marks = if colour % 2 == 0 { vec![0] } else { vec![] };
+        }
+    }
+    out.acceptance_sets = 1;
+    out.acceptance = AcceptanceCondition::buchi();
+    out.acceptance_name = Some((AcceptanceName::Buchi, vec![]));
+    if let Some(sink) = sink {
+        let keep = (0..out.num_states())
+            .map(|q| q != classes[sink])
+            .collect_vec();
+        out.retain_states(&keep);
+    }
+    Some(out)
+}
+
+/// Assigns every state a colour such that colours never increase along edges and a run is
+/// accepting if and only if the colour it eventually stays in is even. Every component
+/// receives the least colour of the right parity that is at least the colour of all
+/// components below it, which makes the colouring only depend on the language of a state.
+/// Returns `None` if the automaton is not weak.
+fn colours(sym: &Symbolic, sink: Option<usize>) -> Option<Vec<usize>> {
+    let mut colours = vec![0; sym.num_states()];
+    let all = vec![true; sym.num_states()];
+    for component in sccs(sym, &all, |_, _| true) {
+        let below = component
+            .iter()
+            .flat_map(|q| &sym.edges[*q])
+            .map(|edge| edge.targets[0] as usize)
+            .filter(|target| !component.contains(target))
+            .map(|target| colours[target])
+            .max();
+        let cyclic = component.iter().any(|q| {
+            sym.edges[*q]
+                .iter()
+                .any(|edge| component.contains(&(edge.targets[0] as usize)))
+        });
+        let colour = if !cyclic {
+            below.unwrap_or(0)
+        } else {
+            let accepting = if sink.is_some_and(|sink| component.contains(&sink)) {
+                false
+            } else {
+                let mut inner = restrict(sym, &component);
+                let accepting = accepting_lasso(&inner).is_some();
+                inner.acceptance = inner.acceptance.dual();
+                if accepting && accepting_lasso(&inner).is_some() {
+                    return None;
+                }
+                accepting
+            };
+            let least = below.unwrap_or(0);
+            if (least % 2 == 0) == accepting {
+                least
+            } else {
+                least + 1
+            }
+        };
+        for q in component {
+            colours[q] = colour;
+        }
+    }
+    Some(colours)
+}
+
+/// Restricts the automaton to the edges inside of a strongly connected component.
+fn restrict(sym: &Symbolic, component: &[usize]) -> Symbolic {
+    let mut out = sym.empty_like(sym.acceptance_sets, sym.acceptance.clone());
+    for _ in component {
+        out.add_state(None);
+    }
+    for (i, q) in component.iter().enumerate() {
+        out.edges[i] = sym.edges[*q]
+            .iter()
+            .filter_map(|edge| {
+                let target = component
+                    .iter()
+                    .position(|p| *p == edge.targets[0] as usize)?;
+                Some(SymbolicEdge {
+                    targets: vec![target as Id],
+                    ..edge.clone()
+                })
+            })
+            .collect();
+    }
+    out.start = vec![vec![0]];
+    out
+}
+
+/// Refines the given partition of the states of a deterministic automaton until all states
+/// in a class move to the same classes under the same guards. If `with_marks` is set,
+/// edges are additionally distinguished by their acceptance sets. Returns the class of
+/// each state, classes are numbered by their first state.
+pub(crate) fn refine(sym: &Symbolic, mut classes: Vec<usize>, with_marks: bool) -> Vec<usize> {
+    let mut count = classes.iter().unique().count();
+    loop {
+        let mut ids: HashMap<(usize, Signature), usize> = HashMap::new();
+        let refined = (0..sym.num_states())
+            .map(|q| {
+                let signature = (classes[q], class_guards(sym, &classes, q, with_marks));
+                let next = ids.len();
+                *ids.entry(signature).or_insert(next)
+            })
+            .collect_vec();
+        classes = refined;
+        if ids.len() == count {
+            return classes;
+        }
+        count = ids.len();
+    }
+}
+
+/// Builds the quotient of a deterministic automaton with respect to a partition computed
+/// by [`refine`] with the same value of `with_marks`.
+pub(crate) fn quotient(sym: &Symbolic, classes: &[usize], with_marks: bool) -> Symbolic {
+    let mut out = sym.empty_like(sym.acceptance_sets, sym.acceptance.clone());
+    let count = classes.iter().max().map_or(0, |max| max + 1);
+    let mut representatives = vec![None; count];
+    for (q, class) in classes.iter().enumerate() {
+        representatives[*class].get_or_insert(q);
+    }
+    for representative in &representatives {
+        let q = representative.expect("every class has a representative");
+        out.add_state(sym.names[q].clone());
+    }
+    for (class, representative) in representatives.into_iter().enumerate() {
+        let q = representative.expect("every class has a representative");
+        out.edges[class] = class_guards(sym, classes, q, with_marks)
+            .into_iter()
+            .map(|((target, marks), guard)| SymbolicEdge {
+                guard,
+                targets: vec![target as Id],
+                marks,
+            })
+            .collect();
+    }
+    out.start = sym
+        .start
+        .iter()
+        .map(|conjunction| {
+            conjunction
+                .iter()
+                .map(|q| classes[*q as usize] as Id)
+                .collect()
+        })
+        .collect();
+    out
+}
+
+/// Collects the guards of all edges leaving `q` by the class of their target and, if
+/// `with_marks` is set, their acceptance sets.
+fn class_guards(sym: &Symbolic, classes: &[usize], q: usize, with_marks: bool) -> Signature {
+    sym.edges[q]
+        .iter()
+        .map(|edge| {
+            let marks = if with_marks {
+                edge.marks.clone()
+            } else {
+                vec![]
+            };
+            ((classes[edge.targets[0] as usize], marks), &edge.guard)
+        })
+        .into_grouping_map_by(|(key, _)| key.clone())
+        .fold(sym.vs.mk_false(), |acc, _, (_, guard)| acc.or(guard))
+        .into_iter()
+        .sorted_by(|(left, _), (right, _)| left.cmp(right))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{language::is_equivalent, HoaAutomaton};
+
+    use super::minimise;
+
+    #[test]
+    fn weak_automaton_is_minimised() {
+        // F a, where the accepting sink is unrolled into a cycle of length two that
+        // only visits the acceptance set on every other edge
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 4
+            Start: 0
+            AP: 1 "a"
+            acc-name: Buchi
+            Acceptance: 1 Inf(0)
+            --BODY--
+            State: 0
+            [!0] 3
+            [0] 1
+            State: 1
+            [t] 2 {0}
+            State: 2
+            [t] 1
+            State: 3
+            [!0] 0
+            [0] 1
+            --END--"#,
+        )
+        .unwrap();
+        let min = minimise(&aut).unwrap();
+        assert_eq!(min.num_states(), Some(2));
+        assert!(is_equivalent(&aut, &min).unwrap().holds());
+    }
+
+    #[test]
+    fn incomplete_safety_automaton() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 3
+            Start: 0
+            AP: 2 "a" "b"
+            Acceptance: 0 t
+            --BODY--
+            State: 0
+            [!0] 1
+            [0] 2
+            State: 1
+            [!0] 0
+            [0] 2
+            State: 2
+            [1] 0
+            --END--"#,
+        )
+        .unwrap();
+        let min = minimise(&aut).unwrap();
+        assert_eq!(min.num_states(), Some(2));
+        assert!(is_equivalent(&aut, &min).unwrap().holds());
+    }
+
+    #[test]
+    fn bisimulation_quotient() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 2
+            Start: 0
+            AP: 1 "a"
+            acc-name: Buchi
+            Acceptance: 1 Inf(0)
+            --BODY--
+            State: 0
+            [0] 1 {0}
+            [!0] 0
+            State: 1
+            [0] 1 {0}
+            [!0] 0
+            --END--"#,
+        )
+        .unwrap();
+        let min = minimise(&aut).unwrap();
+        assert_eq!(min.num_states(), Some(1));
+        assert!(is_equivalent(&aut, &min).unwrap().holds());
+    }
+
+    #[test]
+    fn requires_determinism() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 1
+            Start: 0
+            AP: 1 "a"
+            Acceptance: 0 t
+            --BODY--
+            State: 0
+            [0] 0
+            [t] 0
+            --END--"#,
+        )
+        .unwrap();
+        assert!(minimise(&aut).is_err());
+    }
+}
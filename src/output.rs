@@ -1,20 +1,365 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display, io::Write};
 
+use biodivine_lib_bdd::Bdd;
 use itertools::Itertools;
 
 use crate::{
-    AcceptanceAtom, AcceptanceCondition, AcceptanceInfo, AcceptanceName, AcceptanceSignature,
-    AliasName, Edge, HeaderItem, HoaAutomaton, HoaBool, Label, Property, State, StateConjunction,
+    build_vars,
+    symbolic::{declared_aps, Symbolic},
+    AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, AcceptanceInfo, AcceptanceName,
+    AcceptanceSignature, AliasName, Edge, HeaderItem, HoaAutomaton, HoaBool, Label, Property,
+    State, StateConjunction,
 };
 
+/// Writes the automaton in the HOA format using a [`HoaWriter`] with default options. The
+/// result parses back into an automaton that is equal to `aut`, provided that its labels
+/// have no conjunctions or disjunctions with fewer than two operands. These are written as
+/// their operand or as `t` and `f` and read back as such.
 pub fn to_hoa(aut: &HoaAutomaton) -> String {
-    aut.header()
+    let mut writer = HoaWriter::new(vec![]);
+    writer
+        .write(aut)
+        .expect("writing with the default options cannot fail");
+    String::from_utf8(writer.into_inner()).expect("the output is valid UTF-8")
+}
+
+/// Determines where a [`HoaWriter`] places acceptance sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AcceptancePlacement {
+    /// Acceptance sets are written on the edges.
+    #[default]
+    Transition,
+    /// Acceptance sets are written on the states, provided that all edges leaving each
+    /// state carry the same acceptance sets. Otherwise they are written on the edges.
+    State,
+}
+
+/// Determines how a [`HoaWriter`] obtains the `properties:` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PropertiesMode {
+    /// The properties are copied from the automaton, only the label and acceptance styles
+    /// are adjusted if implicit labels or state-based acceptance are written.
+    #[default]
+    Copy,
+    /// The properties are computed from the automaton, which includes the label and
+    /// acceptance styles as well as `univ-branch`, `deterministic`, `complete` and `colored`.
+    Recompute,
+}
+
+/// Determines which version of the HOA format a [`HoaWriter`] declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormatVersion {
+    /// The version given in the header of the automaton.
+    #[default]
+    Keep,
+    /// Version 1, negated properties are dropped and `controllable-AP:` is an error.
+    V1,
+    /// Version 1.1.
+    V1_1,
+}
+
+/// Writes automata in the HOA format to an [`std::io::Write`]. By default, every header
+/// item, state and edge is written on its own line, labels are explicit, acceptance is
+/// transition-based and properties are copied.
+#[derive(Debug)]
+pub struct HoaWriter<W> {
+    out: W,
+    compact: bool,
+    implicit_labels: bool,
+    acceptance: AcceptancePlacement,
+    aliases: bool,
+    properties: PropertiesMode,
+    version: FormatVersion,
+}
+
+impl<W: Write> HoaWriter<W> {
+    /// Creates a writer with default options.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            compact: false,
+            implicit_labels: false,
+            acceptance: AcceptancePlacement::default(),
+            aliases: false,
+            properties: PropertiesMode::default(),
+            version: FormatVersion::default(),
+        }
+    }
+
+    /// Writes each automaton on a single line.
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Writes implicit labels if every state has exactly one edge per valuation of the
+    /// atomic propositions, listed in the order of the valuations.
+    pub fn implicit_labels(mut self, implicit_labels: bool) -> Self {
+        self.implicit_labels = implicit_labels;
+        self
+    }
+
+    /// Chooses where acceptance sets are placed.
+    pub fn acceptance(mut self, acceptance: AcceptancePlacement) -> Self {
+        self.acceptance = acceptance;
+        self
+    }
+
+    /// Introduces an alias for every compound label that is used more than once.
+    pub fn aliases(mut self, aliases: bool) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Chooses whether properties are copied or recomputed.
+    pub fn properties(mut self, properties: PropertiesMode) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Chooses the declared version of the format.
+    pub fn version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Writes a single automaton, followed by a newline. Fails if the underlying writer
+    /// fails, if properties are recomputed and a label refers to an undeclared atomic
+    /// proposition or if version 1 is chosen and the automaton has controllable atomic
+    /// propositions.
+    pub fn write(&mut self, aut: &HoaAutomaton) -> std::io::Result<()> {
+        let v1 = self.version == FormatVersion::V1;
+        if v1
+            && aut
+                .header()
+                .iter()
+                .any(|item| matches!(item, HeaderItem::ControllableAP(_)))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "controllable-AP requires HOA v1.1",
+            ));
+        }
+        let state_acceptance = self.acceptance == AcceptancePlacement::State
+            && aut.body().iter().all(|state| {
+                state
+                    .edges()
+                    .iter()
+                    .map(|edge| edge.acceptance_signature())
+                    .all_equal()
+            });
+        let implicit = self.implicit_labels && has_implicit_labels(aut);
+        let aliases = if self.aliases && !implicit {
+            aliases(aut)
+        } else {
+            vec![]
+        };
+        let properties = match self.properties {
+            PropertiesMode::Copy => None,
+            PropertiesMode::Recompute => Some(
+                recompute_properties(aut, implicit, state_acceptance)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            ),
+        };
+
+        let mut lines = vec![];
+        let mut properties = properties.map(HeaderItem::Properties);
+        let mut alias_lines = Some(
+            aliases
+                .iter()
+                .map(|(expression, name)| {
+                    HeaderItem::Alias(name.clone(), expression.clone()).to_string()
+                })
+                .collect_vec(),
+        );
+        for item in aut.header() {
+            match item {
+                HeaderItem::Properties(_) if properties.is_some() => {
+                    lines.extend(properties.take().map(|item| item.to_string()))
+                }
+                HeaderItem::Properties(_) if self.properties == PropertiesMode::Recompute => {}
+                HeaderItem::Properties(copied) => {
+                    let adjusted = copied
+                        .iter()
+                        .filter(|property| !v1 || !property.requires_v1_1())
+                        .map(|property| match property {
+                            Property::ExplicitLabels if implicit => Property::ImplicitLabels,
+                            Property::TransitionAcceptance if state_acceptance => {
+                                Property::StateAcceptance
+                            }
+                            property => property.clone(),
+                        })
+                        .collect();
+                    lines.push(HeaderItem::Properties(adjusted).to_string());
+                }
+                HeaderItem::Version(_) if v1 => lines.push(HeaderItem::v1().to_string()),
+                HeaderItem::Version(_) if self.version == FormatVersion::V1_1 => {
+                    lines.push(HeaderItem::Version("v1.1".to_string()).to_string())
+                }
+                item => lines.push(item.to_string()),
+            }
+            if let HeaderItem::AP(_) = item {
+                lines.extend(alias_lines.take().into_iter().flatten());
+            }
+        }
+        lines.extend(properties.map(|item| item.to_string()));
+        // without an AP item, the aliases are declared at the end of the header
+        lines.extend(alias_lines.into_iter().flatten());
+
+        lines.push("--BODY--".to_string());
+        let aliases: HashMap<_, _> = aliases.into_iter().collect();
+        for state in aut.body() {
+            let mut line = format!("State: {}", state.id());
+            if let Some(comment) = state.comment() {
+                line.push_str(&format!(" {}", quoted(comment)));
+            }
+            match state.edges().first() {
+                Some(edge) if state_acceptance && !edge.acceptance_signature().is_empty() => {
+                    line.push_str(&format!(" {}", edge.acceptance_signature()))
+                }
+                _ => {}
+            }
+            lines.push(line);
+            for edge in state.edges() {
+                let mut line = match (implicit, aliases.get(&edge.label().0)) {
+                    (true, _) => String::new(),
+                    (false, Some(name)) => format!("[{}] ", name),
+                    (false, None) => format!("{} ", edge.label()),
+                };
+                line.push_str(&edge.state_conjunction().to_string());
+                if !state_acceptance && !edge.acceptance_signature().is_empty() {
+                    line.push_str(&format!(" {}", edge.acceptance_signature()));
+                }
+                lines.push(line);
+            }
+        }
+        lines.push("--END--".to_string());
+
+        let separator = if self.compact { " " } else { "\n" };
+        writeln!(self.out, "{}", lines.join(separator))
+    }
+}
+
+/// Returns true if every state has exactly one edge per valuation of the atomic
+/// propositions and the i-th edge is labelled with the i-th valuation, where the first
+/// atomic proposition is the least significant bit.
+fn has_implicit_labels(aut: &HoaAutomaton) -> bool {
+    let aps = declared_aps(aut).len();
+    let Some(valuations) = 1usize.checked_shl(aps as u32) else {
+        return false;
+    };
+    let Ok((vs, vars)) = build_vars(aps) else {
+        return false;
+    };
+    let minterm = |i: usize| -> Bdd {
+        vars.iter()
+            .enumerate()
+            .fold(vs.mk_true(), |acc, (bit, var)| match (i >> bit) & 1 {
+                1 => acc.and(&vs.mk_var(*var)),
+                _ => acc.and_not(&vs.mk_var(*var)),
+            })
+    };
+    aut.body().iter().all(|state| {
+        state.edges().len() == valuations
+            && state.edges().iter().enumerate().all(|(i, edge)| {
+                edge.label()
+                    .0
+                    .clone()
+                    .try_into_bdd(&vs, &vars)
+                    .is_ok_and(|guard| guard == minterm(i))
+            })
+    })
+}
+
+/// Assigns a fresh alias to every label that is used more than once and is neither a
+/// constant nor a possibly negated atomic proposition, in the order of first use.
+fn aliases(aut: &HoaAutomaton) -> Vec<(AbstractLabelExpression, AliasName)> {
+    let taken = aut
+        .aliases()
+        .into_iter()
+        .map(|(name, _)| name.0)
+        .collect_vec();
+    let mut names = (0..)
+        .map(|i| format!("l{i}"))
+        .filter(|name| !taken.contains(name));
+    aut.body()
+        .iter()
+        .flat_map(|state| state.edges())
+        .map(|edge| &edge.label().0)
+        .filter(|expression| {
+            !matches!(
+                expression,
+                AbstractLabelExpression::Boolean(_) | AbstractLabelExpression::Integer(_)
+            ) && expression.try_atom().is_none()
+        })
+        .counts_by(|expression| expression.clone())
         .into_iter()
-        .map(|header_item| header_item.to_string())
-        .chain(std::iter::once("--BODY--".to_string()))
-        .chain(aut.body().into_iter().map(|state| state.to_string()))
-        .chain(std::iter::once("--END--".to_string()))
-        .join("\n")
+        .filter(|(_, count)| *count > 1)
+        .map(|(expression, _)| expression)
+        .sorted_by_key(|expression| {
+            aut.body()
+                .iter()
+                .flat_map(|state| state.edges())
+                .position(|edge| edge.label().0 == *expression)
+        })
+        .map(|expression| {
+            let name = names.next().expect("there are infinitely many names");
+            (expression, AliasName(name))
+        })
+        .collect()
+}
+
+/// Computes the properties that [`PropertiesMode::Recompute`] writes.
+fn recompute_properties(
+    aut: &HoaAutomaton,
+    implicit: bool,
+    state_acceptance: bool,
+) -> Result<Vec<Property>, String> {
+    let sym = Symbolic::from_automaton(aut)?;
+    let mut properties = vec![
+        Property::TransLabels,
+        match implicit {
+            true => Property::ImplicitLabels,
+            false => Property::ExplicitLabels,
+        },
+        match state_acceptance {
+            true => Property::StateAcceptance,
+            false => Property::TransitionAcceptance,
+        },
+    ];
+    if sym.is_alternating() {
+        properties.push(Property::UniversalBranching);
+    } else {
+        properties.push(Property::NoUniversalBranching);
+    }
+    if sym.is_deterministic() {
+        properties.push(Property::Deterministic);
+    }
+    let complete = !sym.start.is_empty()
+        && sym.edges.iter().all(|edges| {
+            edges
+                .iter()
+                .fold(sym.vs.mk_false(), |acc, edge| acc.or(&edge.guard))
+                .not()
+                .is_false()
+        });
+    if complete {
+        properties.push(Property::Complete);
+    }
+    if sym.edges.iter().flatten().all(|edge| edge.marks.len() == 1) {
+        properties.push(Property::Colored);
+    }
+    Ok(properties)
+}
+
+/// Wraps a string in double quotes, escaping backslashes and double quotes inside of it.
+pub(crate) fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
 impl Display for HeaderItem {
@@ -23,12 +368,13 @@ impl Display for HeaderItem {
             HeaderItem::Version(version) => write!(f, "HOA: {}", version),
             HeaderItem::States(number) => write!(f, "States: {}", number),
             HeaderItem::Start(state_conj) => write!(f, "Start: {}", state_conj),
-            HeaderItem::AP(aps) => write!(
-                f,
-                "AP: {} {}",
-                aps.len(),
-                aps.iter().map(|ap| format!("\"{}\"", ap)).join(" ")
-            ),
+            HeaderItem::AP(aps) => {
+                write!(f, "AP: {}", aps.len())?;
+                for ap in aps {
+                    write!(f, " {}", quoted(ap))?;
+                }
+                Ok(())
+            }
             HeaderItem::Alias(alias_name, alias_expression) => {
                 write!(f, "Alias: {} {}", alias_name, alias_expression)
             }
@@ -36,14 +382,33 @@ impl Display for HeaderItem {
                 write!(f, "Acceptance: {} {}", number_sets, condition)
             }
             HeaderItem::AcceptanceName(identifier, vec_info) => {
-                write!(f, "acc-name: {} {}", identifier, vec_info.iter().join(" "))
+                write!(f, "acc-name: {}", identifier)?;
+                for info in vec_info {
+                    write!(f, " {}", info)?;
+                }
+                Ok(())
             }
-            HeaderItem::Tool(name, options) => {
-                write!(f, "tool: {} {}", name, options.iter().join(" "))
+            HeaderItem::Tool(name, version) => {
+                write!(f, "tool: {}", quoted(name))?;
+                if let Some(version) = version {
+                    write!(f, " {}", quoted(version))?;
+                }
+                Ok(())
             }
-            HeaderItem::Name(name) => write!(f, "name: {}", name),
+            HeaderItem::Name(name) => write!(f, "name: {}", quoted(name)),
             HeaderItem::Properties(properties) => {
-                write!(f, "properties: {}", properties.iter().join(" "))
+                write!(f, "properties:")?;
+                for property in properties {
+                    write!(f, " {}", property)?;
+                }
+                Ok(())
+            }
+            HeaderItem::ControllableAP(aps) => {
+                write!(f, "controllable-AP:")?;
+                for ap in aps {
+                    write!(f, " {}", ap)?;
+                }
+                Ok(())
             }
         }
     }
@@ -60,30 +425,29 @@ impl Display for AcceptanceInfo {
 
 impl Display for Property {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Property::StateLabels => "state-labels",
-                Property::TransLabels => "trans-labels",
-                Property::ImplicitLabels => "implicit-labels",
-                Property::ExplicitLabels => "explicit-labels",
-                Property::StateAcceptance => "state-acc",
-                Property::TransitionAcceptance => "trans-acc",
-                Property::UniversalBranching => "univ-branch",
-                Property::NoUniversalBranching => "no-univ-branch",
-                Property::Deterministic => "deterministic",
-                Property::Complete => "complete",
-                Property::Unambiguous => "unabmiguous",
-                Property::StutterInvariant => "stutter-invariant",
-                Property::Weak => "weak",
-                Property::VeryWeak => "very-weak",
-                Property::InherentlyWeak => "inherently-weak",
-                Property::Terminal => "terminal",
-                Property::Tight => "tight",
-                Property::Colored => "colored",
-            }
-        )
+        let name = match self {
+            Property::StateLabels => "state-labels",
+            Property::TransLabels => "trans-labels",
+            Property::ImplicitLabels => "implicit-labels",
+            Property::ExplicitLabels => "explicit-labels",
+            Property::StateAcceptance => "state-acc",
+            Property::TransitionAcceptance => "trans-acc",
+            Property::UniversalBranching => "univ-branch",
+            Property::NoUniversalBranching => "no-univ-branch",
+            Property::Deterministic => "deterministic",
+            Property::Complete => "complete",
+            Property::Unambiguous => "unambiguous",
+            Property::StutterInvariant => "stutter-invariant",
+            Property::Weak => "weak",
+            Property::VeryWeak => "very-weak",
+            Property::InherentlyWeak => "inherently-weak",
+            Property::Terminal => "terminal",
+            Property::Tight => "tight",
+            Property::Colored => "colored",
+            Property::SemiDeterministic => "semi-deterministic",
+            Property::Negated(property) => return write!(f, "!{}", property),
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -164,14 +528,18 @@ impl Display for AcceptanceSignature {
 
 impl Display for Edge {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {} {}", self.0, self.1, self.2)
+        write!(f, "{} {}", self.0, self.1)?;
+        if !self.2.is_empty() {
+            write!(f, " {}", self.2)?;
+        }
+        Ok(())
     }
 }
 
 impl Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(acc) = &self.1 {
-            writeln!(f, "State: {} \"{}\"", self.0, acc)?;
+            writeln!(f, "State: {} {}", self.0, quoted(acc))?;
         } else {
             writeln!(f, "State: {}", self.0)?;
         }
@@ -181,3 +549,337 @@ impl Display for State {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        random::{random_automaton, RandomAcceptance, RandomOptions},
+        AbstractLabelExpression, AcceptanceSignature, Body, Edge, Header, HeaderItem, HoaAutomaton,
+        Label, State, StateConjunction,
+    };
+
+    use super::{to_hoa, AcceptancePlacement, FormatVersion, HoaWriter, PropertiesMode};
+
+    fn write(aut: &HoaAutomaton, writer: HoaWriter<Vec<u8>>) -> String {
+        let mut writer = writer;
+        writer.write(aut).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    fn assert_round_trip(aut: &HoaAutomaton) {
+        let printed = to_hoa(aut);
+        assert_eq!(
+            HoaAutomaton::try_from(printed.as_str()).as_ref(),
+            Ok(aut),
+            "{printed}"
+        );
+    }
+
+    #[test]
+    fn round_trip_random_automata() {
+        let kinds = [
+            RandomAcceptance::Buchi,
+            RandomAcceptance::Parity(3),
+            RandomAcceptance::Rabin(2),
+            RandomAcceptance::EmersonLei(4),
+        ];
+        for seed in 0..64 {
+            let options = RandomOptions {
+                states: 1 + seed as usize % 7,
+                aps: seed as usize % 4,
+                density: 0.4,
+                acceptance: kinds[seed as usize % kinds.len()],
+                deterministic: seed % 3 == 0,
+                alternating: seed % 5 == 0,
+            };
+            let aut = random_automaton(&options, seed).unwrap();
+            assert_round_trip(&aut);
+
+            // the same automaton with a name, a tool and named states
+            let mut header = aut.header().iter().cloned().collect::<Vec<_>>();
+            header.push(HeaderItem::Name(format!("random {seed}")));
+            header.push(HeaderItem::Tool(
+                "hoars".to_string(),
+                Some("0.2".to_string()),
+            ));
+            let body = aut
+                .body()
+                .iter()
+                .map(|state| {
+                    State::from_parts(
+                        state.id(),
+                        Some(format!("q{}", state.id())),
+                        state.edges().to_vec(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_round_trip(&HoaAutomaton::from_parts(
+                Header::from_vec(header),
+                Body::from(body),
+            ));
+        }
+    }
+
+    #[test]
+    fn round_trip_escaped_strings() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            name: "say \"hi\""
+            tool: "c:\\bin\\tool" "\v1"
+            States: 1
+            Start: 0
+            AP: 2 "a\"b" "\\"
+            Acceptance: 0 t
+            --BODY--
+            State: 0 "back\\slash"
+            [0 & 1] 0
+            --END--"#,
+        )
+        .unwrap();
+        assert_eq!(aut.aps(), &vec![r#"a"b"#.to_string(), r"\".to_string()]);
+        assert_eq!(aut.body()[0].comment(), Some(r"back\slash"));
+        let printed = to_hoa(&aut);
+        assert!(printed.contains(r#"name: "say \"hi\"""#));
+        assert!(printed.contains(r#"tool: "c:\\bin\\tool" "\\v1""#));
+        assert!(printed.contains(r#"AP: 2 "a\"b" "\\""#));
+        assert_round_trip(&aut);
+    }
+
+    #[test]
+    fn round_trip_nested_labels() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            name: "nested"
+            tool: "some tool"
+            States: 1
+            Start: 0
+            AP: 3 "a" "b" "c"
+            acc-name: Buchi
+            Acceptance: 1 Inf(0)
+            properties: unambiguous
+            --BODY--
+            State: 0 "only state"
+            [(0 | 1) & !(2 & 0)] 0 {0}
+            [!(0 | 1) & (2 & 0)] 0
+            [0 & 1 | !2 | f] 0
+            --END--"#,
+        )
+        .unwrap();
+        let printed = to_hoa(&aut);
+        assert!(printed.contains("name: \"nested\"\n"));
+        assert!(printed.contains("tool: \"some tool\"\n"));
+        assert!(printed.contains("acc-name: Buchi\n"));
+        assert!(printed.contains("[(0 | 1) & !(2 & 0)] 0 {0}\n"));
+        assert!(printed.contains("[0 & 1 | !2 | f] 0\n"));
+        assert_round_trip(&aut);
+    }
+
+    #[test]
+    fn round_trip_empty_properties_and_degenerate_labels() {
+        let aut = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 1\nStart: 0\nAP: 1 \"a\"\nAcceptance: 0 t\nproperties:\n\
+             --BODY--\nState: 0\n[t] 0\n--END--",
+        )
+        .unwrap();
+        assert!(aut.header().contains(&HeaderItem::Properties(vec![])));
+        assert!(to_hoa(&aut).contains("properties:\n"));
+        assert_round_trip(&aut);
+
+        // conjunctions and disjunctions with fewer than two operands are read back as
+        // their operand or as a constant
+        let labels = [
+            (AbstractLabelExpression::Conjunction(vec![]), "t"),
+            (AbstractLabelExpression::Disjunction(vec![]), "f"),
+            (
+                AbstractLabelExpression::Conjunction(vec![AbstractLabelExpression::Integer(0)]),
+                "0",
+            ),
+        ];
+        let edges = labels
+            .iter()
+            .map(|(label, _)| {
+                Edge::from_parts(
+                    Label(label.clone()),
+                    StateConjunction(vec![0]),
+                    AcceptanceSignature(vec![]),
+                )
+            })
+            .collect();
+        let degenerate = HoaAutomaton::from_parts(
+            aut.header().clone(),
+            Body::from(vec![State::from_parts(0, None, edges)]),
+        );
+        let parsed = HoaAutomaton::try_from(to_hoa(&degenerate).as_str()).unwrap();
+        for (edge, (_, written)) in parsed.body()[0].edges().iter().zip(labels) {
+            assert_eq!(edge.label().0.to_string(), written);
+        }
+        assert_eq!(
+            parsed.body()[0].edges()[2].label().0,
+            AbstractLabelExpression::Integer(0)
+        );
+    }
+
+    #[test]
+    fn compact_state_based_output() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 2
+            Start: 0
+            AP: 1 "a"
+            acc-name: Buchi
+            Acceptance: 1 Inf(0)
+            properties: trans-labels explicit-labels trans-acc
+            --BODY--
+            State: 0
+            [!0] 0
+            [0] 1
+            State: 1 "accepting"
+            [!0] 0 {0}
+            [0] 1 {0}
+            --END--"#,
+        )
+        .unwrap();
+        let printed = write(
+            &aut,
+            HoaWriter::new(vec![])
+                .compact(true)
+                .acceptance(AcceptancePlacement::State),
+        );
+        assert_eq!(printed.lines().count(), 1);
+        assert!(printed.contains(" trans-labels explicit-labels state-acc "));
+        assert!(printed.contains(r#"State: 1 "accepting" {0} [!0] 0 [0] 1 --END--"#));
+        let parsed = HoaAutomaton::try_from(printed.as_str()).unwrap();
+        assert_eq!(parsed.body(), aut.body());
+    }
+
+    #[test]
+    fn implicit_labels_and_recomputed_properties() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 1
+            Start: 0
+            AP: 2 "a" "b"
+            acc-name: parity min even 2
+            Acceptance: 2 Inf(0) | Fin(1)
+            properties: state-labels
+            --BODY--
+            State: 0
+            [!0 & !1] 0 {0}
+            [0 & !1] 0 {1}
+            [!0 & 1] 0 {1}
+            [0 & 1] 0 {0}
+            --END--"#,
+        )
+        .unwrap();
+        let printed = write(
+            &aut,
+            HoaWriter::new(vec![])
+                .implicit_labels(true)
+                .properties(PropertiesMode::Recompute),
+        );
+        assert!(printed.contains(
+            "properties: trans-labels implicit-labels trans-acc no-univ-branch deterministic complete colored\n"
+        ));
+        assert!(printed.contains("State: 0\n0 {0}\n0 {1}\n0 {1}\n0 {0}\n--END--\n"));
+        let parsed = HoaAutomaton::try_from(printed.as_str()).unwrap();
+        assert_eq!(parsed.body(), aut.body());
+
+        // labels that are not in the order of the valuations are written explicitly
+        let printed = write(
+            &aut,
+            HoaWriter::new(vec![]).acceptance(AcceptancePlacement::State),
+        );
+        assert!(printed.contains("[!0 & !1] 0 {0}\n"));
+        assert!(printed.contains("properties: state-labels\n"));
+    }
+
+    #[test]
+    fn aliases_for_repeated_labels() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 2
+            Start: 0
+            AP: 2 "a" "b"
+            Acceptance: 0 t
+            --BODY--
+            State: 0
+            [0 & !1] 1
+            [!0] 0
+            State: 1
+            [0 & !1] 0
+            [!0] 1
+            [0 | 1] 1
+            --END--"#,
+        )
+        .unwrap();
+        let printed = write(&aut, HoaWriter::new(vec![]).aliases(true));
+        assert!(printed.contains("AP: 2 \"a\" \"b\"\nAlias: @l0 0 & !1\nAcceptance:"));
+        assert_eq!(printed.matches("[@l0]").count(), 2);
+        assert!(printed.contains("[!0] 0\n"));
+        assert!(printed.contains("[0 | 1] 1\n"));
+        let parsed = HoaAutomaton::try_from(printed.as_str()).unwrap();
+        assert_eq!(parsed.body(), aut.body());
+
+        // without an AP item, the aliases are still declared before they are used
+        let mut header = aut.header().clone();
+        header.retain(|item| !matches!(item, HeaderItem::AP(_)));
+        let aut = HoaAutomaton::from_parts(header, aut.body().clone());
+        let printed = write(&aut, HoaWriter::new(vec![]).aliases(true));
+        assert!(printed.contains("Alias: @l0 0 & !1\n--BODY--\n"));
+        assert_eq!(printed.matches("[@l0]").count(), 2);
+        let parsed = HoaAutomaton::try_from(printed.as_str()).unwrap();
+        assert_eq!(parsed.body(), aut.body());
+
+        // aliases that the automaton already defines are kept, fresh names avoid them
+        let printed = write(&parsed, HoaWriter::new(vec![]).aliases(true));
+        assert!(printed.contains("Alias: @l0 0 & !1\nAlias: @l1 0 & !1\n--BODY--\n"));
+        assert_eq!(printed.matches("[@l1]").count(), 2);
+        let reparsed = HoaAutomaton::try_from(printed.as_str()).unwrap();
+        assert_eq!(reparsed.body(), aut.body());
+    }
+
+    #[test]
+    fn chosen_version() {
+        let input = r#"HOA: v1.1
+States: 1
+AP: 2 "in" "out"
+controllable-AP: 1
+Acceptance: 0 t
+properties: deterministic !complete
+--BODY--
+State: 0
+[0 & 1] 0
+--END--"#;
+        let aut = HoaAutomaton::try_from(input).unwrap();
+        assert_round_trip(&aut);
+        assert!(HoaWriter::new(vec![])
+            .version(FormatVersion::V1)
+            .write(&aut)
+            .is_err());
+
+        let mut header = aut.header().clone();
+        header.retain(|item| !matches!(item, HeaderItem::ControllableAP(_)));
+        let aut = HoaAutomaton::from_parts(header, aut.body().clone());
+        let v1 = write(&aut, HoaWriter::new(vec![]).version(FormatVersion::V1));
+        assert!(v1.starts_with("HOA: v1\n"));
+        assert!(v1.contains("properties: deterministic\n"));
+
+        let v1 = HoaAutomaton::try_from(v1.as_str()).unwrap();
+        let v1_1 = write(&v1, HoaWriter::new(vec![]).version(FormatVersion::V1_1));
+        assert!(v1_1.starts_with("HOA: v1.1\n"));
+
+        let semi_deterministic = HoaAutomaton::try_from(
+            input
+                .replace("controllable-AP: 1\n", "")
+                .replace("!complete", "semi-deterministic")
+                .as_str(),
+        )
+        .unwrap();
+        let v1 = write(
+            &semi_deterministic,
+            HoaWriter::new(vec![]).version(FormatVersion::V1),
+        );
+        assert!(v1.contains("properties: deterministic\n"));
+        assert!(HoaAutomaton::try_from(v1.as_str()).is_ok());
+    }
+}
@@ -0,0 +1,270 @@
+//! Export of Büchi automata as never claims in Promela, the input language of the
+//! [SPIN](https://spinroot.com/) model checker.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, Edge, HeaderItem, HoaAutomaton,
+    Id,
+};
+
+/// A state of the never claim, which is a state of the automaton together with a flag that
+/// says whether it is accepting.
+type Node = (Id, bool);
+
+/// Writes a Büchi automaton as a never claim. States that are accepting are labelled with
+/// the prefix `accept_` and guards refer to the atomic propositions by name. If all edges
+/// leaving each state agree on being accepting, the states of the claim are the states of
+/// the automaton. Otherwise acceptance is moved to the states by splitting each state into
+/// a copy that is entered by accepting edges and one that is entered by the other edges.
+///
+/// Fails unless the acceptance condition is `Inf` of a single set, `t` or `f`, and none of
+/// the initial states or edges is universal.
+pub fn to_never_claim(aut: &HoaAutomaton) -> Result<String, String> {
+    let accepting: Box<dyn Fn(&Edge) -> bool> = match &aut.acceptance().1 {
+        AcceptanceCondition::Inf(AcceptanceAtom::Positive(set)) => {
+            let set = *set;
+            Box::new(move |edge: &Edge| edge.acceptance_signature().contains(&set))
+        }
+        AcceptanceCondition::Boolean(value) => {
+            let value = value.0;
+            Box::new(move |_: &Edge| value)
+        }
+        _ => return Err("Never claims require Büchi acceptance".to_string()),
+    };
+    let start = aut
+        .start()
+        .into_iter()
+        .map(|conjunction| {
+            conjunction
+                .get_singleton()
+                .ok_or("Never claims do not support universal initial states")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if aut
+        .body()
+        .edges()
+        .any(|(_, edge)| edge.state_conjunction().get_singleton().is_none())
+    {
+        return Err("Never claims do not support universal edges".to_string());
+    }
+
+    // a state is accepting if all edges leaving it are, or None if they disagree
+    let state_based: Option<HashMap<Id, bool>> = aut
+        .body()
+        .iter()
+        .map(
+            |state| match state.edges().iter().map(&accepting).dedup().at_most_one() {
+                Ok(accepting) => Some((state.id(), accepting.unwrap_or(false))),
+                Err(_) => None,
+            },
+        )
+        .collect();
+    let node = |state: Id, entered_by: Option<&Edge>| -> Node {
+        match &state_based {
+            Some(accepting) => (state, accepting.get(&state).copied().unwrap_or(false)),
+            None => (state, entered_by.is_some_and(&accepting)),
+        }
+    };
+
+    // the reachable nodes in the order of their discovery, starting with the initial ones
+    let initial = start.iter().map(|state| node(*state, None)).collect_vec();
+    let mut nodes = initial.clone();
+    let mut i = 0;
+    while let Some(&(state, _)) = nodes.get(i) {
+        for edge in aut
+            .body()
+            .state(state)
+            .map_or(&[][..], |state| state.edges())
+        {
+            let target = node(edge.state_conjunction().0[0], Some(edge));
+            if !nodes.contains(&target) {
+                nodes.push(target);
+            }
+        }
+        i += 1;
+    }
+
+    let name = |node: &Node| -> String {
+        let prefix = if node.1 { "accept" } else { "T0" };
+        match initial.as_slice() {
+            [single] if single == node => format!("{prefix}_init"),
+            _ => format!("{prefix}_S{}", node.0),
+        }
+    };
+    let aps = aut.aps();
+    let choices = |state: Id| {
+        aut.body()
+            .state(state)
+            .map_or(&[][..], |state| state.edges())
+            .iter()
+            .filter(|edge| !is_false(&edge.label().0))
+            .map(|edge| {
+                let target = node(edge.state_conjunction().0[0], Some(edge));
+                format!(
+                    "  :: ({}) -> goto {}",
+                    guard(&edge.label().0, aps),
+                    name(&target)
+                )
+            })
+            .collect_vec()
+    };
+    let block = |label: String, choices: Vec<String>| -> Vec<String> {
+        let mut lines = vec![format!("{label}:")];
+        match choices.is_empty() {
+            true => lines.push("  false;".to_string()),
+            false => {
+                lines.push("  if".to_string());
+                lines.extend(choices);
+                lines.push("  fi;".to_string());
+            }
+        }
+        lines
+    };
+
+    let mut lines = vec![];
+    match aut.header().iter().find_map(|item| match item {
+        HeaderItem::Name(name) => Some(name),
+        _ => None,
+    }) {
+        Some(name) => lines.push(format!("never {{ /* {} */", name.replace("*/", "* /"))),
+        None => lines.push("never {".to_string()),
+    }
+    if initial.len() != 1 {
+        let choices = initial
+            .iter()
+            .flat_map(|(state, _)| choices(*state))
+            .collect();
+        lines.extend(block("T0_init".to_string(), choices));
+    }
+    for node in &nodes {
+        lines.extend(block(name(node), choices(node.0)));
+    }
+    lines.push("}".to_string());
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Returns true if the expression is the constant `f`, edges with such a label are never
+/// taken and left out of the claim.
+fn is_false(expression: &AbstractLabelExpression) -> bool {
+    match expression {
+        AbstractLabelExpression::Boolean(value) => !value,
+        AbstractLabelExpression::Disjunction(disjuncts) => disjuncts.iter().all(is_false),
+        _ => false,
+    }
+}
+
+/// Writes a label as a Promela expression. The constants are `1` and `0`, atomic
+/// propositions are written as their name, which is parenthesized unless it is an
+/// identifier so that names like `x > 0` can be used, or as `p` followed by their index if
+/// they have no name.
+fn guard(expression: &AbstractLabelExpression, aps: &[String]) -> String {
+    let nested = |operand: &AbstractLabelExpression| match operand {
+        AbstractLabelExpression::Conjunction(operands)
+        | AbstractLabelExpression::Disjunction(operands)
+            if operands.len() > 1 =>
+        {
+            format!("({})", guard(operand, aps))
+        }
+        _ => guard(operand, aps),
+    };
+    match expression {
+        AbstractLabelExpression::Boolean(true) => "1".to_string(),
+        AbstractLabelExpression::Boolean(false) => "0".to_string(),
+        AbstractLabelExpression::Integer(ap) => match aps.get(*ap as usize) {
+            Some(name)
+                if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                name.clone()
+            }
+            Some(name) => format!("({name})"),
+            None => format!("p{ap}"),
+        },
+        AbstractLabelExpression::Negated(operand) => format!("!{}", nested(operand)),
+        AbstractLabelExpression::Conjunction(operands) => match operands.as_slice() {
+            [] => "1".to_string(),
+            operands => operands.iter().map(nested).join(" && "),
+        },
+        AbstractLabelExpression::Disjunction(operands) => match operands.as_slice() {
+            [] => "0".to_string(),
+            operands => operands.iter().map(nested).join(" || "),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_never_claim;
+    use crate::HoaAutomaton;
+
+    #[test]
+    fn state_based_claim() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+name: "GFa"
+States: 2
+Start: 0
+AP: 2 "a" "x > 0"
+acc-name: Buchi
+Acceptance: 1 Inf(0)
+--BODY--
+State: 0
+[0 & !(1 | t)] 1
+[!0] 0
+[f] 1
+State: 1
+[t] 1 {0}
+--END--"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_never_claim(&aut).unwrap(),
+            "never { /* GFa */\nT0_init:\n  if\n  :: (a && !((x > 0) || 1)) -> goto accept_S1\n  \
+             :: (!a) -> goto T0_init\n  fi;\naccept_S1:\n  if\n  :: (1) -> goto accept_S1\n  fi;\n}\n"
+        );
+    }
+
+    #[test]
+    fn transition_based_claim() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+States: 2
+Start: 0
+Start: 1
+AP: 1 "a"
+Acceptance: 1 Inf(0)
+--BODY--
+State: 0
+[0] 0 {0}
+[!0] 0
+State: 1
+[f] 1
+--END--"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_never_claim(&aut).unwrap(),
+            "never {\nT0_init:\n  if\n  :: (a) -> goto accept_S0\n  :: (!a) -> goto T0_S0\n  fi;\n\
+             T0_S0:\n  if\n  :: (a) -> goto accept_S0\n  :: (!a) -> goto T0_S0\n  fi;\n\
+             T0_S1:\n  false;\n\
+             accept_S0:\n  if\n  :: (a) -> goto accept_S0\n  :: (!a) -> goto T0_S0\n  fi;\n}\n"
+        );
+    }
+
+    #[test]
+    fn unsupported_automata() {
+        let parity = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 1\nStart: 0\nAcceptance: 2 Inf(0) & Inf(1)\n--BODY--\nState: 0\n[t] 0\n--END--",
+        )
+        .unwrap();
+        assert!(to_never_claim(&parity).is_err());
+        let universal = HoaAutomaton::try_from(
+            "HOA: v1\nStates: 2\nStart: 0\nAcceptance: 0 t\n--BODY--\nState: 0\n[t] 0 & 1\nState: 1\n--END--",
+        )
+        .unwrap();
+        assert!(to_never_claim(&universal).is_err());
+    }
+}
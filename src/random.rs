@@ -0,0 +1,351 @@
+use biodivine_lib_bdd::Bdd;
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    symbolic::{Symbolic, SymbolicEdge},
+    AcceptanceCondition, AcceptanceInfo, AcceptanceName, HoaAutomaton, HoaBool, Id, Property,
+};
+
+/// The kind of acceptance condition a random automaton is generated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomAcceptance {
+    /// A Büchi condition, every edge is in the single acceptance set with probability 1/2.
+    Buchi,
+    /// A `parity min even` condition with the given number of priorities, every edge
+    /// carries exactly one priority.
+    Parity(u32),
+    /// A Rabin condition with the given number of pairs.
+    Rabin(u32),
+    /// A random positive boolean combination of `Fin` and `Inf` atoms that uses each of the
+    /// given number of acceptance sets exactly once.
+    EmersonLei(u32),
+}
+
+/// Describes the shape of the automata produced by [`random_automaton`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomOptions {
+    /// The number of states, state `0` is always initial.
+    pub states: usize,
+    /// The number of atomic propositions, which are named `p0`, `p1` and so on.
+    pub aps: usize,
+    /// The probability with which an edge between two states is added. In deterministic
+    /// automata it determines the expected number of successors of each state instead.
+    pub density: f64,
+    /// The kind of acceptance condition.
+    pub acceptance: RandomAcceptance,
+    /// If set, the guards of the edges leaving a state partition the alphabet, so the
+    /// automaton is deterministic and complete unless it is also alternating.
+    pub deterministic: bool,
+    /// If set, some edges and the initial condition may use universal branching.
+    pub alternating: bool,
+}
+
+impl Default for RandomOptions {
+    fn default() -> Self {
+        Self {
+            states: 5,
+            aps: 2,
+            density: 0.3,
+            acceptance: RandomAcceptance::Buchi,
+            deterministic: false,
+            alternating: false,
+        }
+    }
+}
+
+/// Generates a random automaton with transition-based acceptance and explicit transition
+/// labels. The same options and seed always produce the same automaton, which makes the
+/// generator suitable for property-based tests and benchmarks.
+///
+/// Fails if the density is not a probability between `0` and `1`.
+pub fn random_automaton(options: &RandomOptions, seed: u64) -> Result<HoaAutomaton, String> {
+    if !(0.0..=1.0).contains(&options.density) {
+        return Err(format!(
+            "The density {} is not between 0 and 1",
+            options.density
+        ));
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (acceptance_sets, acceptance, acceptance_name) = acceptance(options.acceptance, &mut rng);
+    let aps = (0..options.aps).map(|i| format!("p{i}")).collect();
+    let mut sym = Symbolic::new(aps, acceptance_sets, acceptance)?;
+    sym.acceptance_name = acceptance_name;
+
+    let n = options.states;
+    for _ in 0..n {
+        sym.add_state(None);
+    }
+    if n > 0 {
+        let mut start = vec![0];
+        if options.alternating && n > 1 && rng.gen_ratio(1, 3) {
+            start.push(rng.gen_range(1..n) as Id);
+        }
+        sym.start.push(start);
+    }
+
+    for q in 0..n {
+        let successors = if options.deterministic {
+            let degree = 1 + (1..n).filter(|_| rng.gen_bool(options.density)).count();
+            let cells = partition(&sym, degree, &mut rng);
+            let targets = (0..n).collect_vec();
+            cells
+                .into_iter()
+                .zip(
+                    targets
+                        .choose_multiple(&mut rng, degree)
+                        .copied()
+                        .collect_vec(),
+                )
+                .collect_vec()
+        } else {
+            let mut successors = vec![];
+            for target in 0..n {
+                if rng.gen_bool(options.density) {
+                    successors.push((guard(&sym, &mut rng), target));
+                }
+            }
+            if successors.is_empty() {
+                successors.push((guard(&sym, &mut rng), rng.gen_range(0..n)));
+            }
+            successors
+        };
+
+        for (guard, target) in successors {
+            let mut targets = vec![target as Id];
+            if options.alternating && n > 1 && rng.gen_ratio(1, 3) {
+                let other = rng.gen_range(0..n - 1);
+                targets.push((if other >= target { other + 1 } else { other }) as Id);
+            }
+            let marks = marks(options.acceptance, acceptance_sets, &mut rng);
+            sym.edges[q].push(SymbolicEdge {
+                guard,
+                targets,
+                marks,
+            });
+        }
+    }
+
+    let mut properties = vec![
+        Property::TransLabels,
+        Property::ExplicitLabels,
+        Property::TransitionAcceptance,
+    ];
+    if sym.is_alternating() {
+        properties.push(Property::UniversalBranching);
+    } else if options.deterministic {
+        properties.push(Property::Deterministic);
+    }
+    if options.deterministic {
+        properties.push(Property::Complete);
+    }
+    if matches!(options.acceptance, RandomAcceptance::Parity(_)) {
+        properties.push(Property::Colored);
+    }
+    Ok(sym.into_automaton(properties))
+}
+
+/// Builds the acceptance condition together with the number of sets and its name.
+fn acceptance(
+    kind: RandomAcceptance,
+    rng: &mut StdRng,
+) -> (
+    usize,
+    AcceptanceCondition,
+    Option<(AcceptanceName, Vec<AcceptanceInfo>)>,
+) {
+    match kind {
+        RandomAcceptance::Buchi => (
+            1,
+            AcceptanceCondition::buchi(),
+            Some((AcceptanceName::Buchi, vec![])),
+        ),
+        RandomAcceptance::Parity(priorities) => {
+            let priorities = priorities.max(1);
+            (
+                priorities as usize,
+                AcceptanceCondition::parity(priorities),
+                Some((
+                    AcceptanceName::Parity,
+                    vec![
+                        AcceptanceInfo::Identifier("min".to_string()),
+                        AcceptanceInfo::Identifier("even".to_string()),
+                        AcceptanceInfo::Int(priorities),
+                    ],
+                )),
+            )
+        }
+        RandomAcceptance::Rabin(pairs) => {
+            let condition = (0..pairs)
+                .map(|i| {
+                    AcceptanceCondition::id_fin(2 * i).and(AcceptanceCondition::id_inf(2 * i + 1))
+                })
+                .reduce(|left, right| left.or(right))
+                .unwrap_or(AcceptanceCondition::Boolean(HoaBool(false)));
+            (
+                2 * pairs as usize,
+                condition,
+                Some((AcceptanceName::Rabin, vec![AcceptanceInfo::Int(pairs)])),
+            )
+        }
+        RandomAcceptance::EmersonLei(sets) => {
+            let mut conditions = (0..sets)
+                .map(|i| match rng.gen_bool(0.5) {
+                    true => AcceptanceCondition::id_fin(i),
+                    false => AcceptanceCondition::id_inf(i),
+                })
+                .collect_vec();
+            conditions.shuffle(rng);
+            while conditions.len() > 1 {
+                let left = conditions.swap_remove(rng.gen_range(0..conditions.len()));
+                let right = conditions.swap_remove(rng.gen_range(0..conditions.len()));
+                conditions.push(match rng.gen_bool(0.5) {
+                    true => left.and(right),
+                    false => left.or(right),
+                });
+            }
+            let condition = conditions
+                .pop()
+                .unwrap_or(AcceptanceCondition::Boolean(HoaBool(true)));
+            (sets as usize, condition, None)
+        }
+    }
+}
+
+/// Picks the acceptance sets of a single edge.
+fn marks(kind: RandomAcceptance, acceptance_sets: usize, rng: &mut StdRng) -> Vec<Id> {
+    match kind {
+        RandomAcceptance::Buchi => match rng.gen_bool(0.5) {
+            true => vec![0],
+            false => vec![],
+        },
+        RandomAcceptance::Parity(_) => vec![rng.gen_range(0..acceptance_sets) as Id],
+        RandomAcceptance::Rabin(_) | RandomAcceptance::EmersonLei(_) => (0..acceptance_sets)
+            .filter(|_| rng.gen_ratio(1, 3))
+            .map(|set| set as Id)
+            .collect(),
+    }
+}
+
+/// Returns a random cube over the atomic propositions, occasionally joined with a second
+/// one. The result is never unsatisfiable.
+fn guard(sym: &Symbolic, rng: &mut StdRng) -> Bdd {
+    let cube = |rng: &mut StdRng| {
+        sym.vars
+            .iter()
+            .fold(sym.vs.mk_true(), |acc, var| match rng.gen_range(0..3) {
+                0 => acc,
+                1 => acc.and(&sym.vs.mk_var(*var)),
+                _ => acc.and_not(&sym.vs.mk_var(*var)),
+            })
+    };
+    let guard = cube(rng);
+    match rng.gen_ratio(1, 4) {
+        true => guard.or(&cube(rng)),
+        false => guard,
+    }
+}
+
+/// Splits the alphabet into pairwise disjoint, non-empty guards. Fewer than `count` guards
+/// are returned if there are not enough letters.
+fn partition(sym: &Symbolic, count: usize, rng: &mut StdRng) -> Vec<Bdd> {
+    let mut cells = vec![sym.vs.mk_true()];
+    if sym.vars.is_empty() {
+        return cells;
+    }
+    for _ in 0..8 * count {
+        if cells.len() >= count {
+            break;
+        }
+        let i = rng.gen_range(0..cells.len());
+        let var = sym
+            .vs
+            .mk_var(*sym.vars.choose(rng).expect("there is a variable"));
+        let (inside, outside) = (cells[i].and(&var), cells[i].and_not(&var));
+        if !inside.is_false() && !outside.is_false() {
+            cells[i] = inside;
+            cells.push(outside);
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbolic::Symbolic;
+
+    use super::{random_automaton, RandomAcceptance, RandomOptions};
+
+    #[test]
+    fn reproducible() {
+        let options = RandomOptions {
+            states: 8,
+            aps: 3,
+            acceptance: RandomAcceptance::EmersonLei(4),
+            alternating: true,
+            ..Default::default()
+        };
+        assert_eq!(random_automaton(&options, 7), random_automaton(&options, 7));
+        assert_ne!(random_automaton(&options, 7), random_automaton(&options, 8));
+    }
+
+    #[test]
+    fn invalid_density() {
+        for density in [-0.1, 1.5, f64::NAN] {
+            let options = RandomOptions {
+                density,
+                ..Default::default()
+            };
+            assert!(random_automaton(&options, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn deterministic_and_complete() {
+        for (seed, acceptance) in [
+            RandomAcceptance::Buchi,
+            RandomAcceptance::Parity(4),
+            RandomAcceptance::Rabin(2),
+            RandomAcceptance::EmersonLei(3),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let options = RandomOptions {
+                states: 10,
+                aps: 3,
+                density: 0.4,
+                acceptance,
+                deterministic: true,
+                alternating: false,
+            };
+            let aut = random_automaton(&options, seed as u64).unwrap();
+            assert_eq!(aut.num_states(), Some(10));
+            assert!(aut.verify().is_ok());
+            let sym = Symbolic::from_automaton(&aut).unwrap();
+            assert!(sym.is_deterministic());
+            for edges in &sym.edges {
+                let covered = edges
+                    .iter()
+                    .fold(sym.vs.mk_false(), |acc, edge| acc.or(&edge.guard));
+                assert!(covered.not().is_false());
+            }
+        }
+    }
+
+    #[test]
+    fn alternating_automata_verify() {
+        let options = RandomOptions {
+            states: 6,
+            aps: 2,
+            density: 0.5,
+            acceptance: RandomAcceptance::Parity(3),
+            alternating: true,
+            ..Default::default()
+        };
+        assert!((0..20)
+            .map(|seed| random_automaton(&options, seed).unwrap())
+            .inspect(|aut| assert!(aut.verify().is_ok()))
+            .any(|aut| Symbolic::from_automaton(&aut).unwrap().is_alternating()));
+    }
+}
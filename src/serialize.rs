@@ -0,0 +1,218 @@
+//! Implementations of [`Serialize`] and [`Deserialize`] for the types whose serialized form
+//! differs from what deriving would give. Label expressions, properties and acceptance
+//! names are written in HOA syntax, states and edges as objects with named fields and the
+//! spans of parsed automata are left out. The resulting JSON schema is described in the
+//! README and must stay stable.
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    AbstractLabelExpression, AcceptanceName, AcceptanceSignature, Body, Edge, Header, HoaAutomaton,
+    Id, Label, Property, State, StateConjunction,
+};
+
+impl Serialize for AbstractLabelExpression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AbstractLabelExpression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Property {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Property {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Property::try_from(String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for AcceptanceName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AcceptanceName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        AcceptanceName::try_from(String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// The serialized form of an [`Edge`].
+#[derive(Serialize, Deserialize)]
+struct EdgeData<L, T, M> {
+    label: L,
+    to: T,
+    #[serde(default)]
+    marks: M,
+}
+
+impl Serialize for Edge {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EdgeData {
+            label: &self.0,
+            to: &self.1,
+            marks: &self.2,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Edge {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let EdgeData { label, to, marks } =
+            EdgeData::<Label, StateConjunction, Option<AcceptanceSignature>>::deserialize(
+                deserializer,
+            )?;
+        Ok(Edge(
+            label,
+            to,
+            marks.unwrap_or(AcceptanceSignature(vec![])),
+        ))
+    }
+}
+
+/// The serialized form of a [`State`].
+#[derive(Serialize, Deserialize)]
+struct StateData<C, E> {
+    id: Id,
+    #[serde(default)]
+    comment: C,
+    #[serde(default)]
+    edges: E,
+}
+
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateData {
+            id: self.0,
+            comment: &self.1,
+            edges: &self.2,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let StateData { id, comment, edges } =
+            StateData::<Option<String>, Vec<Edge>>::deserialize(deserializer)?;
+        Ok(State(id, comment, edges))
+    }
+}
+
+impl Serialize for Body {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<State>::deserialize(deserializer).map(Body::from)
+    }
+}
+
+/// The serialized form of a [`HoaAutomaton`].
+#[derive(Serialize, Deserialize)]
+struct AutomatonData<H, B> {
+    header: H,
+    body: B,
+}
+
+impl Serialize for HoaAutomaton {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        AutomatonData {
+            header: &self.header,
+            body: &self.body,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HoaAutomaton {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let AutomatonData { header, body } =
+            AutomatonData::<Header, Body>::deserialize(deserializer)?;
+        Ok(HoaAutomaton::from_parts(header, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        input::from_hoa,
+        random::{random_automaton, RandomOptions},
+        AbstractLabelExpression, HoaAutomaton,
+    };
+
+    const INPUT: &str = r#"HOA: v1.1
+name: "example"
+tool: "hoars" "0.2"
+States: 2
+Start: 0
+AP: 2 "a" "b"
+acc-name: generalized-Buchi 2
+Acceptance: 2 Inf(0) & Inf(!1)
+properties: trans-labels !complete
+--BODY--
+State: 0 "init"
+[0 & !1] 1 {0 1}
+State: 1
+[t] 0
+--END--"#;
+
+    const JSON: &str = r#"{"header":[{"HOA":"v1.1"},{"name":"example"},{"tool":["hoars","0.2"]},{"States":2},{"Start":[0]},{"AP":["a","b"]},{"acc-name":["generalized-Buchi",[2]]},{"Acceptance":[2,{"And":[{"Inf":{"Positive":0}},{"Inf":{"Negative":1}}]}]},{"properties":["trans-labels","!complete"]}],"body":[{"id":0,"comment":"init","edges":[{"label":"0 & !1","to":[1],"marks":[0,1]}]},{"id":1,"comment":null,"edges":[{"label":"t","to":[0],"marks":[]}]}]}"#;
+
+    #[test]
+    fn schema() {
+        let aut = from_hoa(INPUT).unwrap();
+        assert_eq!(serde_json::to_string(&aut).unwrap(), JSON);
+        assert_eq!(serde_json::from_str::<HoaAutomaton>(JSON).unwrap(), aut);
+
+        let minimal = r#"{"header":[{"HOA":"v1"},{"Acceptance":[0,{"Boolean":true}]}],
+            "body":[{"id":0,"edges":[{"label":"!(0 | 1)","to":[0]}]}]}"#;
+        let aut = serde_json::from_str::<HoaAutomaton>(minimal).unwrap();
+        assert!(aut.body()[0].comment().is_none());
+        assert!(aut.body()[0].edges()[0].acceptance_signature().is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        for seed in 0..50 {
+            let aut = random_automaton(&RandomOptions::default(), seed).unwrap();
+            let json = serde_json::to_string(&aut).unwrap();
+            assert_eq!(serde_json::from_str::<HoaAutomaton>(&json).unwrap(), aut);
+        }
+        for input in [
+            include_str!("../hoa/simpledpa.hoa"),
+            include_str!("../hoa/manysipmledpa.hoa"),
+        ] {
+            for aut in crate::parse_hoa_automata(input) {
+                let json = serde_json::to_string_pretty(&aut).unwrap();
+                assert_eq!(serde_json::from_str::<HoaAutomaton>(&json).unwrap(), aut);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_labels() {
+        let label = |json: &str| serde_json::from_str::<AbstractLabelExpression>(json);
+        assert_eq!(label(r#""!(0 & t)""#).unwrap().to_string(), "!(0 & t)");
+        assert!(label(r#""0 &""#).is_err());
+        assert!(label(r#""a""#).is_err());
+        let deep = format!("\"{}0{}\"", "(".repeat(1000), ")".repeat(1000));
+        assert!(label(&deep).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use itertools::Itertools;
+
+use crate::{
+    symbolic::{Symbolic, SymbolicEdge},
+    AcceptanceAtom, AcceptanceCondition, HoaAutomaton, Id, Property,
+};
+
+/// Summarizes the effect of [`reduce`] on an automaton.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Reduction {
+    /// The number of states that were merged with another state or became unreachable.
+    pub states_removed: usize,
+    /// The number of edges that were merged with another edge or removed because they were
+    /// subsumed by another edge.
+    pub edges_removed: usize,
+}
+
+/// Reduces an automaton without universal branching using direct simulation, the result
+/// accepts the same language. A state `p` simulates `q` if `p` can match every edge of `q`
+/// with an edge whose acceptance sets are at least as good and whose target simulates the
+/// target of the matched edge. For conditions that only use positive `Inf` atoms, such as
+/// (generalized) Büchi conditions, more acceptance sets are at least as good, otherwise the
+/// acceptance sets have to coincide.
+///
+/// States that simulate each other are merged, edges with the same target and acceptance
+/// sets are combined, edges that are subsumed by another edge of the same state are removed
+/// and states that become unreachable are dropped.
+pub fn reduce(aut: &HoaAutomaton) -> Result<(HoaAutomaton, Reduction), String> {
+    let sym = Symbolic::from_automaton(aut)?;
+    if sym.is_alternating() {
+        return Err("Simulation does not support universal branching".to_string());
+    }
+    let states_before = sym.num_states();
+    let edges_before = sym.edges.iter().map(|edges| edges.len()).sum::<usize>();
+
+    let monotone = only_positive_inf(&sym.acceptance);
+    let covers = |marks: &[Id], other: &[Id]| {
+        if monotone {
+            marks.iter().all(|m| other.contains(m))
+        } else {
+            marks == other
+        }
+    };
+    let simulates = direct_simulation(&sym, covers);
+
+    // Merge states that simulate each other.
+    let mut classes = vec![usize::MAX; sym.num_states()];
+    let mut representatives = vec![];
+    for q in 0..sym.num_states() {
+        if classes[q] == usize::MAX {
+            for p in q..sym.num_states() {
+                if classes[p] == usize::MAX && simulates[q][p] && simulates[p][q] {
+                    classes[p] = representatives.len();
+                }
+            }
+            representatives.push(q);
+        }
+    }
+
+    let mut quotient = sym.empty_like(sym.acceptance_sets, sym.acceptance.clone());
+    quotient.acceptance_name = sym.acceptance_name.clone();
+    for q in &representatives {
+        quotient.add_state(sym.names[*q].clone());
+    }
+    for (q, edges) in sym.edges.iter().enumerate() {
+        for edge in edges {
+            let target = classes[edge.targets[0] as usize] as Id;
+            let merged = &mut quotient.edges[classes[q]];
+            match merged
+                .iter_mut()
+                .find(|other| other.targets[0] == target && other.marks == edge.marks)
+            {
+                Some(other) => other.guard = other.guard.or(&edge.guard),
+                None => merged.push(SymbolicEdge {
+                    guard: edge.guard.clone(),
+                    targets: vec![target],
+                    marks: edge.marks.clone(),
+                }),
+            }
+        }
+    }
+    quotient.start = sym
+        .start
+        .iter()
+        .map(|conjunction| vec![classes[conjunction[0] as usize] as Id])
+        .unique()
+        .collect();
+
+    // Remove edges that lead to a little brother of the target of another edge.
+    let class_simulates =
+        |c: Id, d: Id| simulates[representatives[c as usize]][representatives[d as usize]];
+    let subsumes = |bigger: &SymbolicEdge, smaller: &SymbolicEdge| {
+        smaller.guard.and_not(&bigger.guard).is_false()
+            && covers(&smaller.marks, &bigger.marks)
+            && class_simulates(smaller.targets[0], bigger.targets[0])
+    };
+    for edges in quotient.edges.iter_mut() {
+        let keep = (0..edges.len())
+            .map(|i| {
+                !(0..edges.len()).any(|j| {
+                    i != j
+                        && subsumes(&edges[j], &edges[i])
+                        && (j < i || !subsumes(&edges[i], &edges[j]))
+                })
+            })
+            .collect_vec();
+        let mut keep = keep.into_iter();
+        edges.retain(|_| keep.next().unwrap());
+    }
+    quotient.remove_unreachable();
+
+    let reduction = Reduction {
+        states_removed: states_before - quotient.num_states(),
+        edges_removed: edges_before
+            - quotient
+                .edges
+                .iter()
+                .map(|edges| edges.len())
+                .sum::<usize>(),
+    };
+    Ok((
+        quotient.into_automaton(vec![
+            Property::TransLabels,
+            Property::ExplicitLabels,
+            Property::TransitionAcceptance,
+        ]),
+        reduction,
+    ))
+}
+
+/// Computes the direct simulation preorder as the greatest fixpoint of the matching
+/// condition, `simulates[q][p]` holds if `p` simulates `q`.
+fn direct_simulation<F>(sym: &Symbolic, covers: F) -> Vec<Vec<bool>>
+where
+    F: Fn(&[Id], &[Id]) -> bool,
+{
+    let n = sym.num_states();
+    let mut simulates = vec![vec![true; n]; n];
+    loop {
+        let mut changed = false;
+        for q in 0..n {
+            for p in 0..n {
+                if q == p || !simulates[q][p] {
+                    continue;
+                }
+                let matched = sym.edges[q].iter().all(|edge| {
+                    let answers = sym.edges[p]
+                        .iter()
+                        .filter(|other| {
+                            covers(&edge.marks, &other.marks)
+                                && simulates[edge.targets[0] as usize][other.targets[0] as usize]
+                        })
+                        .fold(sym.vs.mk_false(), |acc, other| acc.or(&other.guard));
+                    edge.guard.and_not(&answers).is_false()
+                });
+                if !matched {
+                    simulates[q][p] = false;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return simulates;
+        }
+    }
+}
+
+/// Returns true if the condition only consists of positive `Inf` atoms and constants, in
+/// which case visiting more acceptance sets can never hurt.
+fn only_positive_inf(condition: &AcceptanceCondition) -> bool {
+    match condition {
+        AcceptanceCondition::Inf(AcceptanceAtom::Positive(_)) | AcceptanceCondition::Boolean(_) => {
+            true
+        }
+        AcceptanceCondition::Fin(_) | AcceptanceCondition::Inf(AcceptanceAtom::Negative(_)) => {
+            false
+        }
+        AcceptanceCondition::And(left, right) | AcceptanceCondition::Or(left, right) => {
+            only_positive_inf(left) && only_positive_inf(right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{language::is_included_in, HoaAutomaton};
+
+    use super::{reduce, Reduction};
+
+    #[test]
+    fn merges_equivalent_states() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 4
+            Start: 0
+            AP: 1 "a"
+            acc-name: Buchi
+            Acceptance: 1 Inf(0)
+            --BODY--
+            State: 0
+            [0] 1 {0}
+            [0] 2 {0}
+            [!0] 0
+            State: 1
+            [t] 0
+            State: 2
+            [t] 0
+            State: 3
+            [t] 3
+            --END--"#,
+        )
+        .unwrap();
+        let (reduced, reduction) = reduce(&aut).unwrap();
+        assert_eq!(reduced.num_states(), Some(2));
+        assert_eq!(
+            reduction,
+            Reduction {
+                states_removed: 2,
+                edges_removed: 3
+            }
+        );
+    }
+
+    #[test]
+    fn removes_subsumed_edges() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 3
+            Start: 0
+            AP: 1 "a"
+            acc-name: Buchi
+            Acceptance: 1 Inf(0)
+            --BODY--
+            State: 0
+            [0] 1
+            [t] 2 {0}
+            State: 1
+            [t] 1
+            State: 2
+            [t] 2 {0}
+            --END--"#,
+        )
+        .unwrap();
+        let (reduced, reduction) = reduce(&aut).unwrap();
+        assert_eq!(reduced.num_states(), Some(1));
+        assert_eq!(reduction.states_removed, 2);
+        assert_eq!(reduction.edges_removed, 3);
+        assert!(is_included_in(&aut, &reduced).unwrap().holds());
+    }
+
+    #[test]
+    fn exact_marks_for_fin() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 3
+            Start: 0
+            AP: 1 "a"
+            acc-name: co-Buchi
+            Acceptance: 1 Fin(0)
+            --BODY--
+            State: 0
+            [0] 1
+            [t] 2 {0}
+            State: 1
+            [t] 1
+            State: 2
+            [t] 2 {0}
+            --END--"#,
+        )
+        .unwrap();
+        let (_, reduction) = reduce(&aut).unwrap();
+        assert_eq!(reduction, Reduction::default());
+    }
+}
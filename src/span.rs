@@ -0,0 +1,93 @@
+use crate::lexer::Span;
+
+/// Records where the elements of a parsed [`crate::HoaAutomaton`] are located in the input.
+/// Spans are ranges of character (not byte) offsets, which is what [`ariadne`] expects.
+/// They refer to the elements as they were parsed and are not updated when the automaton
+/// is modified afterwards.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Spans {
+    pub(crate) header: Vec<Span>,
+    pub(crate) states: Vec<Span>,
+    pub(crate) edges: Vec<Vec<Span>>,
+}
+
+impl Spans {
+    /// Returns the span of the header item at the given position in the header, position
+    /// `0` is the `HOA:` item.
+    pub fn header_item(&self, index: usize) -> Option<&Span> {
+        self.header.get(index)
+    }
+
+    /// Returns the span of the state at the given position in the body, which ranges from
+    /// `State:` to the end of its last edge.
+    pub fn state(&self, index: usize) -> Option<&Span> {
+        self.states.get(index)
+    }
+
+    /// Returns the span of the given edge of the state at the given position in the body.
+    pub fn edge(&self, state: usize, edge: usize) -> Option<&Span> {
+        self.edges.get(state)?.get(edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HoaAutomaton;
+
+    const INPUT: &str = r#"HOA: v1
+States: 2
+Start: 0
+AP: 1 "a"
+Acceptance: 1 Inf(0)
+--BODY--
+State: 1
+[t] 1 {0}
+State: 0 "init"
+[0] 1
+[!0] 0
+--END--"#;
+
+    fn slice(span: &std::ops::Range<usize>) -> String {
+        INPUT.chars().skip(span.start).take(span.len()).collect()
+    }
+
+    #[test]
+    fn spans_point_into_the_input() {
+        let aut = HoaAutomaton::try_from(INPUT).unwrap();
+        let spans = aut.spans().unwrap();
+        assert_eq!(slice(spans.header_item(0).unwrap()), "HOA: v1");
+        assert_eq!(slice(spans.header_item(4).unwrap()), "Acceptance: 1 Inf(0)");
+        assert!(spans.header_item(5).is_none());
+
+        // states are sorted by their id, their spans move along
+        assert_eq!(aut.body()[0].id(), 0);
+        assert_eq!(
+            slice(spans.state(0).unwrap()),
+            "State: 0 \"init\"\n[0] 1\n[!0] 0"
+        );
+        assert_eq!(slice(spans.edge(0, 1).unwrap()), "[!0] 0");
+        assert_eq!(slice(spans.edge(1, 0).unwrap()), "[t] 1 {0}");
+        assert!(spans.edge(1, 1).is_none());
+    }
+
+    #[test]
+    fn spans_are_ignored_by_equality() {
+        let aut = HoaAutomaton::try_from(INPUT).unwrap();
+        let rebuilt = HoaAutomaton::from_parts(aut.header().clone(), aut.body().clone());
+        assert!(rebuilt.spans().is_none());
+        assert_eq!(aut, rebuilt);
+    }
+
+    #[cfg(feature = "ariadne")]
+    #[test]
+    fn diagnostics_at_spans() {
+        use crate::input::render_diagnostic;
+
+        let aut = HoaAutomaton::try_from(INPUT).unwrap();
+        let span = aut.spans().unwrap().edge(0, 0).unwrap().clone();
+        let report = render_diagnostic(INPUT, span, "edge leaves the automaton", false);
+        assert!(report.contains("edge leaves the automaton"));
+        assert!(report.contains(":10:1"));
+        assert!(report.contains("10 │ [0] 1"));
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use biodivine_lib_bdd::Bdd;
+use itertools::Itertools;
+
+use crate::{
+    minimisation::{quotient, refine},
+    symbolic::{Symbolic, SymbolicEdge},
+    AcceptanceCondition, AcceptanceName, HoaAutomaton, Id, Property,
+};
+
+/// Determinises a safety automaton with a symbolic subset construction and minimises the
+/// result. The input may not use universal branching and has to be a safety automaton in
+/// the following sense: some states are rejecting sinks, i.e. all of their edges are
+/// self-loops carrying the same acceptance sets which do not satisfy the acceptance
+/// condition, and all other edges carry the same acceptance sets, which do satisfy the
+/// acceptance condition. This covers `Acceptance: 0 t` as well as co-Büchi automata with a
+/// rejecting sink. If the acceptance condition is `t`, the acceptance sets are ignored.
+///
+/// The result is a minimal, deterministic and complete co-Büchi automaton in which all
+/// missing transitions of the subset construction lead to a single rejecting sink.
+pub fn subset_construction(aut: &HoaAutomaton) -> Result<HoaAutomaton, String> {
+    let nba = Symbolic::from_automaton(aut)?;
+    if nba.is_alternating() {
+        return Err("Subset construction does not support universal branching".to_string());
+    }
+    let live = live_states(&nba)?;
+
+    let mut dfa = nba.empty_like(1, AcceptanceCondition::id_fin(0));
+    let start: BTreeSet<Id> = nba
+        .start
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|q| live[*q as usize])
+        .collect();
+    if !start.is_empty() {
+        let mut ids = HashMap::new();
+        let mut queue = VecDeque::new();
+        ids.insert(start.clone(), dfa.add_state(None));
+        dfa.start.push(vec![0]);
+        queue.push_back(start);
+
+        while let Some(macrostate) = queue.pop_front() {
+            let source = ids[&macrostate];
+            for (guard, successor) in successors(&nba, &live, &macrostate) {
+                let target = *ids.entry(successor.clone()).or_insert_with(|| {
+                    queue.push_back(successor);
+                    dfa.add_state(None)
+                });
+                dfa.edges[source as usize].push(SymbolicEdge {
+                    guard,
+                    targets: vec![target],
+                    marks: vec![],
+                });
+            }
+        }
+    }
+
+    // All states of the subset construction are accepting and missing transitions are
+    // rejecting, so language equivalence coincides with bisimilarity.
+    let classes = refine(&dfa, vec![0; dfa.num_states()], false);
+    let mut out = quotient(&dfa, &classes, false);
+    complete(&mut out);
+    out.acceptance_name = Some((AcceptanceName::CoBuchi, vec![]));
+    Ok(out.into_automaton(vec![
+        Property::TransLabels,
+        Property::ExplicitLabels,
+        Property::TransitionAcceptance,
+        Property::Deterministic,
+        Property::Complete,
+    ]))
+}
+
+/// Computes the states from which there is an infinite run that avoids all rejecting
+/// sinks. Fails if the automaton is not a safety automaton as described in
+/// [`subset_construction`].
+fn live_states(nba: &Symbolic) -> Result<Vec<bool>, String> {
+    let condition = &nba.acceptance;
+    let trivial = matches!(condition, AcceptanceCondition::Boolean(_));
+
+    let sinks = (0..nba.num_states())
+        .map(|q| {
+            let edges = &nba.edges[q];
+            !trivial
+                && !edges.is_empty()
+                && edges.iter().all(|edge| {
+                    edge.targets == [q as Id]
+                        && edge.marks == edges[0].marks
+                        && !condition.satisfied_by(&edge.marks)
+                })
+        })
+        .collect_vec();
+
+    let mut live = match condition {
+        AcceptanceCondition::Boolean(value) => vec![value.0; nba.num_states()],
+        _ => sinks.iter().map(|sink| !sink).collect(),
+    };
+    if !trivial {
+        let mut signatures = nba
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(q, _)| live[*q])
+            .flat_map(|(_, edges)| edges)
+            .filter(|edge| live[edge.targets[0] as usize])
+            .map(|edge| &edge.marks)
+            .unique();
+        if let Some(marks) = signatures.next() {
+            if signatures.next().is_some() {
+                return Err(
+                    "Not a safety automaton, edges outside of the rejecting sinks carry different acceptance sets"
+                        .to_string(),
+                );
+            }
+            if !condition.satisfied_by(marks) {
+                live = vec![false; nba.num_states()];
+            }
+        }
+    }
+
+    // Remove states which cannot continue into an infinite run.
+    loop {
+        let dead = (0..nba.num_states())
+            .filter(|q| {
+                live[*q]
+                    && !nba.edges[*q]
+                        .iter()
+                        .any(|edge| !edge.guard.is_false() && live[edge.targets[0] as usize])
+            })
+            .collect_vec();
+        if dead.is_empty() {
+            return Ok(live);
+        }
+        for q in dead {
+            live[q] = false;
+        }
+    }
+}
+
+/// Symbolically computes the successors of a macrostate, the returned guards are pairwise
+/// disjoint and successor sets are never empty.
+fn successors(
+    nba: &Symbolic,
+    live: &[bool],
+    macrostate: &BTreeSet<Id>,
+) -> Vec<(Bdd, BTreeSet<Id>)> {
+    let mut guards: HashMap<Id, Bdd> = HashMap::new();
+    for edge in macrostate.iter().flat_map(|q| &nba.edges[*q as usize]) {
+        let target = edge.targets[0];
+        if live[target as usize] {
+            let guard = guards.entry(target).or_insert_with(|| nba.vs.mk_false());
+            *guard = guard.or(&edge.guard);
+        }
+    }
+
+    let mut cells = vec![(nba.vs.mk_true(), BTreeSet::new())];
+    for (target, guard) in guards.into_iter().sorted_by_key(|(target, _)| *target) {
+        cells = cells
+            .into_iter()
+            .flat_map(|(cell, set)| {
+                let inside = cell.and(&guard);
+                let outside = cell.and_not(&guard);
+                let mut extended = set.clone();
+                extended.insert(target);
+                [(inside, extended), (outside, set)]
+            })
+            .filter(|(cell, _)| !cell.is_false())
+            .collect();
+    }
+
+    cells
+        .into_iter()
+        .filter(|(_, set)| !set.is_empty())
+        .into_grouping_map_by(|(_, set)| set.clone())
+        .fold(nba.vs.mk_false(), |acc, _, (cell, _)| acc.or(&cell))
+        .into_iter()
+        .map(|(set, guard)| (guard, set))
+        .sorted_by(|(_, left), (_, right)| left.cmp(right))
+        .collect()
+}
+
+/// Redirects all missing transitions into a rejecting sink, which is added if necessary.
+/// An automaton without initial state is made to start in the sink.
+fn complete(dfa: &mut Symbolic) {
+    let missing = (0..dfa.num_states())
+        .map(|q| {
+            dfa.edges[q]
+                .iter()
+                .fold(dfa.vs.mk_true(), |acc, edge| acc.and_not(&edge.guard))
+        })
+        .collect_vec();
+    if dfa.start.is_empty() || missing.iter().any(|guard| !guard.is_false()) {
+        let sink = dfa.add_state(None);
+        dfa.edges[sink as usize].push(SymbolicEdge {
+            guard: dfa.vs.mk_true(),
+            targets: vec![sink],
+            marks: vec![0],
+        });
+        for (q, guard) in missing.into_iter().enumerate() {
+            if !guard.is_false() {
+                dfa.edges[q].push(SymbolicEdge {
+                    guard,
+                    targets: vec![sink],
+                    marks: vec![],
+                });
+            }
+        }
+        if dfa.start.is_empty() {
+            dfa.start.push(vec![sink]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HoaAutomaton, Property};
+
+    use super::subset_construction;
+
+    fn assert_deterministic(aut: &HoaAutomaton) {
+        let (vs, vars) = crate::build_vars(aut.num_aps()).unwrap();
+        for state in aut.body().iter() {
+            let guards: Vec<_> = state
+                .edges()
+                .iter()
+                .map(|edge| edge.label().0.clone().try_into_bdd(&vs, &vars).unwrap())
+                .collect();
+            for (i, left) in guards.iter().enumerate() {
+                for right in &guards[i + 1..] {
+                    assert!(left.and(right).is_false());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn all_states_accepting() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 3
+            Start: 0
+            AP: 1 "a"
+            Acceptance: 0 t
+            --BODY--
+            State: 0
+            [t] 0
+            [0] 1
+            State: 1
+            [0] 1
+            [!0] 2
+            State: 2
+            --END--"#,
+        )
+        .unwrap();
+        let det = subset_construction(&aut).unwrap();
+        assert_eq!(det.num_states(), Some(1));
+        assert_eq!(det.body()[0].edges().len(), 1);
+    }
+
+    #[test]
+    fn co_buchi_with_rejecting_sink() {
+        // G(a -> X b) with two redundant copies of each state
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 5
+            Start: 0
+            AP: 2 "a" "b"
+            acc-name: co-Buchi
+            Acceptance: 1 Fin(0)
+            --BODY--
+            State: 0
+            [!0] 0
+            [!0] 3
+            [0] 1
+            State: 1
+            [1 & !0] 0
+            [1 & 0] 1
+            [!1] 4
+            State: 2
+            [1 & !0] 3
+            [1 & 0] 2
+            [!1] 4
+            State: 3
+            [!0] 3
+            [0] 1
+            [0] 2
+            State: 4
+            [t] 4 {0}
+            --END--"#,
+        )
+        .unwrap();
+        let det = subset_construction(&aut).unwrap();
+        assert_eq!(det.num_states(), Some(3));
+        assert_deterministic(&det);
+        assert!(det.header().iter().any(|item| matches!(
+            item,
+            crate::HeaderItem::Properties(properties) if properties.contains(&Property::Complete)
+        )));
+    }
+
+    #[test]
+    fn rejects_liveness() {
+        let aut = HoaAutomaton::try_from(
+            r#"HOA: v1
+            States: 1
+            Start: 0
+            AP: 1 "a"
+            Acceptance: 1 Inf(0)
+            --BODY--
+            State: 0
+            [0] 0 {0}
+            [!0] 0
+            --END--"#,
+        )
+        .unwrap();
+        assert!(subset_construction(&aut).is_err());
+    }
+}
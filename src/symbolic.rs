@@ -0,0 +1,324 @@
+use biodivine_lib_bdd::{Bdd, BddVariable, BddVariableSet};
+use itertools::Itertools;
+
+use crate::{
+    build_vars, AbstractLabelExpression, AcceptanceCondition, AcceptanceInfo, AcceptanceName,
+    AcceptanceSignature, Body, Edge, Header, HeaderItem, HoaAutomaton, Id, Label, Property, State,
+    StateConjunction,
+};
+
+/// An edge of a [`Symbolic`] automaton, the label is stored as a [`Bdd`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SymbolicEdge {
+    pub(crate) guard: Bdd,
+    pub(crate) targets: Vec<Id>,
+    pub(crate) marks: Vec<Id>,
+}
+
+/// A dense view of a [`HoaAutomaton`] that the algorithms of this crate operate on. States
+/// are indexed by their [`Id`] and every label is turned into a [`Bdd`] over one variable
+/// per atomic proposition.
+#[derive(Debug, Clone)]
+pub(crate) struct Symbolic {
+    pub(crate) vs: BddVariableSet,
+    pub(crate) vars: Vec<BddVariable>,
+    pub(crate) aps: Vec<String>,
+    pub(crate) start: Vec<Vec<Id>>,
+    pub(crate) acceptance_sets: usize,
+    pub(crate) acceptance: AcceptanceCondition,
+    pub(crate) acceptance_name: Option<(AcceptanceName, Vec<AcceptanceInfo>)>,
+    pub(crate) names: Vec<Option<String>>,
+    pub(crate) edges: Vec<Vec<SymbolicEdge>>,
+}
+
+impl Symbolic {
+    /// Creates an automaton without states over the given atomic propositions. Fails if
+    /// there are more atomic propositions than BDDs support.
+    pub(crate) fn new(
+        aps: Vec<String>,
+        acceptance_sets: usize,
+        acceptance: AcceptanceCondition,
+    ) -> Result<Self, String> {
+        let (vs, vars) = build_vars(aps.len())?;
+        Ok(Self {
+            vs,
+            vars,
+            aps,
+            start: vec![],
+            acceptance_sets,
+            acceptance,
+            acceptance_name: None,
+            names: vec![],
+            edges: vec![],
+        })
+    }
+
+    /// Creates an automaton without states over the atomic propositions of this one, so
+    /// that the guards of both can be combined.
+    pub(crate) fn empty_like(
+        &self,
+        acceptance_sets: usize,
+        acceptance: AcceptanceCondition,
+    ) -> Self {
+        Self {
+            vs: self.vs.clone(),
+            vars: self.vars.clone(),
+            aps: self.aps.clone(),
+            start: vec![],
+            acceptance_sets,
+            acceptance,
+            acceptance_name: None,
+            names: vec![],
+            edges: vec![],
+        }
+    }
+
+    /// Builds the symbolic view of the given automaton. Fails if the acceptance
+    /// condition is missing or a label refers to an undeclared atomic proposition.
+    pub(crate) fn from_automaton(aut: &HoaAutomaton) -> Result<Self, String> {
+        Self::from_automaton_over(aut, declared_aps(aut))
+    }
+
+    /// Like [`Symbolic::from_automaton`], but labels are expressed over the given list of
+    /// atomic propositions, which has to contain all propositions declared by `aut`.
+    pub(crate) fn from_automaton_over(
+        aut: &HoaAutomaton,
+        aps: Vec<String>,
+    ) -> Result<Self, String> {
+        let (acceptance_sets, acceptance) = aut
+            .header()
+            .iter()
+            .find_map(|item| match item {
+                HeaderItem::Acceptance(n, condition) => Some((*n as usize, condition.clone())),
+                _ => None,
+            })
+            .ok_or("Acceptance header is missing!".to_string())?;
+
+        let mut out = Self::new(aps, acceptance_sets, acceptance)?;
+        out.acceptance_name = aut
+            .header()
+            .iter()
+            .find_map(|item| item.try_acceptance_name())
+            .map(|(name, info)| (name.clone(), info.to_vec()));
+        out.start = aut
+            .header()
+            .iter()
+            .filter_map(|item| match item {
+                HeaderItem::Start(conjunction) => Some(conjunction.0.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let size = aut
+            .body()
+            .iter()
+            .map(|state| state.id() as usize + 1)
+            .chain(aut.header().count_states())
+            .chain(out.start.iter().flatten().map(|q| *q as usize + 1))
+            .max()
+            .unwrap_or(0);
+        out.names = vec![None; size];
+        out.edges = vec![vec![]; size];
+
+        let vars = declared_aps(aut)
+            .iter()
+            .map(|ap| match out.aps.iter().position(|other| other == ap) {
+                Some(i) => Ok(out.vars[i]),
+                None => Err(format!("Atomic proposition {ap} is not declared")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for state in aut.body() {
+            out.names[state.id() as usize] = state.comment().map(|c| c.to_string());
+            for edge in state.edges() {
+                let guard = edge.label().0.clone().try_into_bdd(&out.vs, &vars)?;
+                let targets = edge.state_conjunction().0.clone();
+                if let Some(q) = targets.iter().find(|q| **q as usize >= size) {
+                    return Err(format!("Edge target {q} is not a state"));
+                }
+                out.edges[state.id() as usize].push(SymbolicEdge {
+                    guard,
+                    targets,
+                    marks: edge
+                        .acceptance_signature()
+                        .iter()
+                        .copied()
+                        .sorted()
+                        .collect(),
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    /// Adds a new state without edges and returns its id.
+    pub(crate) fn add_state(&mut self, name: Option<String>) -> Id {
+        self.names.push(name);
+        self.edges.push(vec![]);
+        (self.edges.len() - 1) as Id
+    }
+
+    pub(crate) fn num_states(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns true if there is at most one initial state, no universal branching and the
+    /// guards of the edges leaving each state are pairwise disjoint.
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.start.len() <= 1
+            && !self.is_alternating()
+            && self.edges.iter().all(|edges| {
+                edges.iter().enumerate().all(|(i, left)| {
+                    edges[i + 1..]
+                        .iter()
+                        .all(|right| left.guard.and(&right.guard).is_false())
+                })
+            })
+    }
+
+    /// Returns true if some edge or the initial condition uses universal branching.
+    pub(crate) fn is_alternating(&self) -> bool {
+        self.start.iter().any(|conjunction| conjunction.len() > 1)
+            || self
+                .edges
+                .iter()
+                .flatten()
+                .any(|edge| edge.targets.len() > 1)
+    }
+
+    /// Marks all states that are reachable from an initial state.
+    pub(crate) fn reachable(&self) -> Vec<bool> {
+        let mut seen = vec![false; self.num_states()];
+        let mut stack = self
+            .start
+            .iter()
+            .flatten()
+            .map(|q| *q as usize)
+            .collect_vec();
+        while let Some(q) = stack.pop() {
+            if !std::mem::replace(&mut seen[q], true) {
+                stack.extend(
+                    self.edges[q]
+                        .iter()
+                        .filter(|edge| !edge.guard.is_false())
+                        .flat_map(|edge| edge.targets.iter().map(|t| *t as usize)),
+                );
+            }
+        }
+        seen
+    }
+
+    /// Removes all states that are not reachable from an initial state and renumbers the
+    /// remaining ones, preserving their relative order.
+    pub(crate) fn remove_unreachable(&mut self) {
+        self.retain_states(&self.reachable());
+    }
+
+    /// Keeps only the states `q` for which `keep[q]` holds and renumbers them, preserving
+    /// their relative order. Edges and initial conjunctions that refer to a removed state
+    /// are dropped as well.
+    pub(crate) fn retain_states(&mut self, keep: &[bool]) {
+        let mut ids = vec![None; self.num_states()];
+        let mut next = 0;
+        for (q, kept) in keep.iter().enumerate() {
+            if *kept {
+                ids[q] = Some(next);
+                next += 1;
+            }
+        }
+        let rename = |targets: &[Id]| -> Option<Vec<Id>> {
+            targets.iter().map(|t| ids[*t as usize]).collect()
+        };
+        let (names, edges) = std::mem::take(&mut self.names)
+            .into_iter()
+            .zip(std::mem::take(&mut self.edges))
+            .zip(keep)
+            .filter(|(_, kept)| **kept)
+            .map(|((name, edges), _)| {
+                let edges = edges
+                    .into_iter()
+                    .filter(|edge| !edge.guard.is_false())
+                    .filter_map(|edge| {
+                        Some(SymbolicEdge {
+                            targets: rename(&edge.targets)?,
+                            ..edge
+                        })
+                    })
+                    .collect_vec();
+                (name, edges)
+            })
+            .unzip();
+        self.names = names;
+        self.edges = edges;
+        self.start = self
+            .start
+            .iter()
+            .filter_map(|conjunction| rename(conjunction))
+            .collect();
+    }
+
+    /// Turns a guard back into a [`Label`].
+    pub(crate) fn label(&self, guard: &Bdd) -> Label {
+        Label(AbstractLabelExpression::from_bdd(guard, &self.vars))
+    }
+
+    /// Converts back into a [`HoaAutomaton`] with explicit transition labels and
+    /// transition-based acceptance, additionally declaring the given properties.
+    pub(crate) fn into_automaton(self, properties: Vec<Property>) -> HoaAutomaton {
+        let mut header = vec![
+            HeaderItem::v1(),
+            HeaderItem::States(self.num_states() as Id),
+        ];
+        header.extend(
+            self.start
+                .iter()
+                .map(|conjunction| HeaderItem::Start(StateConjunction(conjunction.clone()))),
+        );
+        header.push(HeaderItem::AP(self.aps.clone()));
+        if let Some((name, info)) = &self.acceptance_name {
+            header.push(HeaderItem::AcceptanceName(name.clone(), info.clone()));
+        }
+        header.push(HeaderItem::Acceptance(
+            self.acceptance_sets as Id,
+            self.acceptance.clone(),
+        ));
+        if !properties.is_empty() {
+            header.push(HeaderItem::Properties(properties));
+        }
+
+        let body = self
+            .edges
+            .iter()
+            .zip(self.names.iter())
+            .enumerate()
+            .map(|(id, (edges, name))| {
+                State::from_parts(
+                    id as Id,
+                    name.clone(),
+                    edges
+                        .iter()
+                        .map(|edge| {
+                            Edge::from_parts(
+                                self.label(&edge.guard),
+                                StateConjunction(edge.targets.clone()),
+                                AcceptanceSignature(edge.marks.clone()),
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect_vec();
+
+        HoaAutomaton::from_parts(Header::from_vec(header), Body::from(body))
+    }
+}
+
+/// Returns the atomic propositions declared in the header of `aut`.
+pub(crate) fn declared_aps(aut: &HoaAutomaton) -> Vec<String> {
+    aut.header()
+        .iter()
+        .find_map(|item| match item {
+            HeaderItem::AP(aps) => Some(aps.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
@@ -1,10 +1,12 @@
 use chumsky::{prelude::*, select};
 
 use crate::{
+    error::{self, ParseErrorKind},
     AbstractLabelExpression, AcceptanceAtom, AcceptanceCondition, AcceptanceInfo,
     AcceptanceSignature, HoaBool, Id, StateConjunction, Token,
 };
 
+#[allow(clippy::result_large_err)]
 #[allow(unused)]
 pub fn header() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
     select! {
@@ -12,6 +14,7 @@ pub fn header() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
     }
 }
 
+#[allow(clippy::result_large_err)]
 pub fn boolean() -> impl Parser<Token, bool, Error = Simple<Token>> + Clone {
     select! {
         Token::Identifier(id) if id == *"t" => true,
@@ -19,22 +22,40 @@ pub fn boolean() -> impl Parser<Token, bool, Error = Simple<Token>> + Clone {
     }
 }
 
+/// Parses a non-negative integer. One that does not fit into an [`Id`] is reported and
+/// replaced by [`Id::MAX`], so that parsing can go on.
+#[allow(clippy::result_large_err)]
 pub fn integer() -> impl Parser<Token, Id, Error = Simple<Token>> + Clone {
     select! {
-        Token::Int(n) => n.parse().unwrap(),
+        Token::Int(n) => n,
     }
+    .validate(|n, span, emit| to_id(&n, span, emit))
 }
 
+fn to_id(n: &str, span: crate::Span, emit: &mut dyn FnMut(Simple<Token>)) -> Id {
+    n.parse().unwrap_or_else(|_| {
+        emit(error::custom(
+            ParseErrorKind::IntegerOverflow,
+            span,
+            format!("Integer {n} does not fit into {} bits", Id::BITS),
+        ));
+        Id::MAX
+    })
+}
+
+#[allow(clippy::result_large_err)]
 pub fn text() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
     select! {
         Token::Text(txt) => txt,
     }
 }
 
+#[allow(clippy::result_large_err)]
 pub fn identifier() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
     select! { Token::Identifier(ident) => ident }
 }
 
+#[allow(clippy::result_large_err)]
 pub fn alias_name() -> impl Parser<Token, String, Error = Simple<Token>> + Clone {
     select! { Token::Alias(aname) => aname }
 }
@@ -53,19 +74,28 @@ pub fn acceptance_signature() -> impl Parser<Token, AcceptanceSignature, Error =
         .map(AcceptanceSignature)
 }
 
+#[allow(clippy::result_large_err)]
 pub fn acceptance_info() -> impl Parser<Token, AcceptanceInfo, Error = Simple<Token>> {
     select! {
         Token::Identifier(ident) => AcceptanceInfo::Identifier(ident),
-        Token::Int(n) => AcceptanceInfo::Int(n.parse().unwrap())
     }
+    .or(select! { Token::Int(n) => n }
+        .validate(|n, span, emit| AcceptanceInfo::Int(to_id(&n, span, emit))))
 }
 
+#[allow(clippy::result_large_err)]
 pub fn label_expression() -> impl Parser<Token, AbstractLabelExpression, Error = Simple<Token>> {
     recursive(|label_expression| {
         let value = boolean()
-            .map(|b| AbstractLabelExpression::Boolean(b))
-            .or(integer().map(|i| AbstractLabelExpression::Integer(i as u16)));
-        // .or(alias_name().map(|aname| LabelExpression::Alias(AliasName(aname))));
+            .map(AbstractLabelExpression::Boolean)
+            .or(integer().map(AbstractLabelExpression::Integer))
+            // defined aliases are expanded before parsing
+            .or(alias_name().try_map(|name, span| {
+                Err::<AbstractLabelExpression, _>(Simple::custom(
+                    span,
+                    format!("The alias @{name} is not defined"),
+                ))
+            }));
 
         let atom = value
             .or(label_expression.delimited_by(just(Token::Paren('(')), just(Token::Paren(')'))));
@@ -82,31 +112,19 @@ pub fn label_expression() -> impl Parser<Token, AbstractLabelExpression, Error =
             });
 
         let conjunction = unary
-            .clone()
-            .map(|x| vec![x])
-            .then(just(Token::Op('&')).then(unary).repeated())
-            .foldl(|mut acc, (_tok, expr)| {
-                acc.push(expr);
-                acc
-            })
-            .map(|acc| match acc.len() {
-                0 => panic!("we do not expect this to happen..."),
-                1 => acc.into_iter().next().unwrap(),
-                _ => AbstractLabelExpression::Conjunction(acc),
+            .separated_by(just(Token::Op('&')))
+            .at_least(1)
+            .map(|acc| match <[_; 1]>::try_from(acc) {
+                Ok([single]) => single,
+                Err(acc) => AbstractLabelExpression::Conjunction(acc),
             });
 
         conjunction
-            .clone()
-            .map(|conj| vec![conj])
-            .then(just(Token::Op('|')).then(conjunction).repeated())
-            .foldl(|mut acc, (_tok, rhs)| {
-                acc.push(rhs);
-                acc
-            })
-            .map(|acc| match acc.len() {
-                0 => panic!("it seems we need to deal with the case where this is empty"),
-                1 => acc.into_iter().next().unwrap(),
-                _ => AbstractLabelExpression::Disjunction(acc),
+            .separated_by(just(Token::Op('|')))
+            .at_least(1)
+            .map(|acc| match <[_; 1]>::try_from(acc) {
+                Ok([single]) => single,
+                Err(acc) => AbstractLabelExpression::Disjunction(acc),
             })
     })
 }